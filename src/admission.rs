@@ -0,0 +1,235 @@
+// admission control, a task is only let in when it can get a concurrency slot and a token from
+// its ratelimiter at the same instant
+//
+// waiting on the ratelimiter first and on the semaphore after would spend the token while the
+// task sits in the semaphore queue, wasting a whole interval of the task type for nothing
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use governor::RateLimiter;
+use governor::clock::Clock as _;
+use governor::middleware::NoOpMiddleware;
+use governor::state::{InMemoryState, NotKeyed};
+use tokio::sync::Notify;
+use tokio::sync::futures::Notified;
+use crate::clock::Clock;
+use crate::event::Phase;
+use crate::Job;
+use crate::job::{due, urgency};
+
+pub(crate) type Limiter = RateLimiter<NotKeyed, InMemoryState, LimiterClock, NoOpMiddleware<Duration>>;
+
+// the ratelimiters read the time from the executor's clock rather than the system's
+#[derive(Clone)]
+pub(crate) struct LimiterClock(pub(crate) Arc<dyn Clock>);
+
+impl governor::clock::Clock for LimiterClock {
+    type Instant = Duration;
+
+    fn now(&self) -> Duration {
+        self.0.now()
+    }
+}
+
+struct Waiter {
+    ticket: u64,
+    task: String,
+    priority: i32,
+    deadline: Option<Duration>,
+    since: Duration, // when it started waiting, for aging
+    granted: bool,
+    blocked: Phase, // what it is waiting on, as of the last pass
+    wait: Option<Duration>, // until its ratelimiter frees up, none when it waits for a slot
+}
+
+struct State {
+    running: usize,
+    waiters: Vec<Waiter>, // oldest first, they are let in by priority, then deadline, then age
+    next_ticket: u64,
+}
+
+pub(crate) struct Admission {
+    concurrency: usize,
+    limiters: HashMap<String, Limiter>,
+    clock: Arc<dyn Clock>,
+    aging: Duration,
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+// a concurrency slot, released on drop
+pub(crate) struct Permit<'a> {
+    admission: &'a Admission,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.admission.release();
+    }
+}
+
+// removes the waiter if `acquire` is cancelled, giving back the slot if it had been granted
+struct Pending<'a> {
+    admission: &'a Admission,
+    ticket: u64,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        let mut state = self.admission.state.lock().expect("admission lock poisoned");
+        let Some(i) = state.waiters.iter().position(|w| w.ticket == self.ticket) else { return };
+        if state.waiters.remove(i).granted {
+            state.running -= 1;
+            self.admission.grant(&mut state);
+            drop(state);
+            self.admission.notify.notify_waiters();
+        }
+    }
+}
+
+impl Admission {
+    pub(crate) fn new(concurrency: usize, limiters: HashMap<String, Limiter>, clock: Arc<dyn Clock>, aging: Duration) -> Self {
+        Self {
+            concurrency,
+            limiters,
+            clock,
+            aging,
+            state: Mutex::new(State { running: 0, waiters: Vec::new(), next_ticket: 0 }),
+            notify: Notify::new(),
+        }
+    }
+
+    // hands out slots and tokens to the waiters, most urgent first, then earliest deadline first,
+    // then oldest first, a token is only taken from a ratelimiter when a slot is free for it,
+    // returns whether anything was granted
+    fn grant(&self, state: &mut State) -> bool {
+        let now = self.clock.now();
+        let top = state.waiters.iter().filter(|w| !w.granted).map(|w| w.priority).max().unwrap_or_default();
+        let mut order: Vec<(i32, Duration, u64, usize)> = state.waiters.iter().enumerate()
+            .filter(|(_, w)| !w.granted)
+            .map(|(i, w)| (-urgency(w.priority, now.saturating_sub(w.since), self.aging, top), due(w.deadline), w.ticket, i))
+            .collect();
+        order.sort_unstable();
+
+        let mut granted = false;
+        let mut blocked: HashMap<String, Duration> = HashMap::new(); // task types with no token left
+        for (_, _, _, i) in order {
+            let waiter = &mut state.waiters[i];
+            if let Some(&wait) = blocked.get(waiter.task.as_str()) {
+                (waiter.blocked, waiter.wait) = (Phase::WaitingRateLimit, Some(wait));
+                continue;
+            }
+            if state.running >= self.concurrency {
+                (waiter.blocked, waiter.wait) = (Phase::WaitingSlot, None);
+                continue;
+            }
+            let limiter = &self.limiters[&waiter.task];
+            match limiter.check() {
+                Ok(()) => {
+                    waiter.granted = true;
+                    state.running += 1;
+                    granted = true;
+                }
+                Err(not_until) => {
+                    let wait = not_until.wait_time_from(limiter.clock().now());
+                    blocked.insert(waiter.task.clone(), wait);
+                    (waiter.blocked, waiter.wait) = (Phase::WaitingRateLimit, Some(wait));
+                }
+            }
+        }
+        granted
+    }
+
+    fn release(&self) {
+        let mut state = self.state.lock().expect("admission lock poisoned");
+        state.running -= 1;
+        self.grant(&mut state);
+        drop(state);
+        self.notify.notify_waiters();
+    }
+
+    // waits until the task can start, `on_wait` is told what it is waiting on each time it is
+    // checked again
+    pub(crate) async fn acquire(&self, job: &Job, on_wait: impl Fn(Phase)) -> Permit<'_> {
+        let ticket = {
+            let mut state = self.state.lock().expect("admission lock poisoned");
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.waiters.push(Waiter {
+                ticket,
+                task: job.task.clone(),
+                priority: job.priority,
+                deadline: job.deadline,
+                since: self.clock.now(),
+                granted: false,
+                blocked: Phase::WaitingSlot,
+                wait: None,
+            });
+            ticket
+        };
+        let pending = Pending { admission: self, ticket };
+
+        loop {
+            let notified = self.notify.notified();
+            let mut notified = std::pin::pin!(notified);
+            notified.as_mut().enable(); // so a release between the check and the wait isn't missed
+
+            let (blocked, wait, others) = {
+                let mut state = self.state.lock().expect("admission lock poisoned");
+                let others = self.grant(&mut state);
+                let i = state.waiters.iter().position(|w| w.ticket == ticket).expect("waiter is registered");
+                if state.waiters[i].granted {
+                    state.waiters.remove(i);
+                    drop(state);
+                    std::mem::forget(pending); // nothing to clean up anymore
+                    if others {
+                        self.notify.notify_waiters();
+                    }
+                    return Permit { admission: self };
+                }
+                (state.waiters[i].blocked, state.waiters[i].wait, others)
+            };
+            if others {
+                self.notify.notify_waiters();
+            }
+            on_wait(blocked);
+
+            match wait {
+                Some(wait) => tokio::select! {
+                    _ = notified => {}
+                    _ = self.clock.sleep(wait) => {}
+                },
+                None => notified.await,
+            }
+        }
+    }
+
+    // resolves on the next release of a slot, enable it before checking `has_slot` so a release in
+    // between isn't missed
+    pub(crate) fn released(&self) -> Notified<'_> {
+        self.notify.notified()
+    }
+
+    // whether `try_acquire` could get a slot right now
+    pub(crate) fn has_slot(&self) -> bool {
+        let state = self.state.lock().expect("admission lock poisoned");
+        state.running < self.concurrency && state.waiters.is_empty()
+    }
+
+    // admission without waiting, on failure tells how long until the ratelimiter frees up, or
+    // none if it is a slot that is missing
+    pub(crate) fn try_acquire(&self, task: &str) -> Result<Permit<'_>, Option<Duration>> {
+        let mut state = self.state.lock().expect("admission lock poisoned");
+        if state.running >= self.concurrency || !state.waiters.is_empty() {
+            return Err(None);
+        }
+        let limiter = &self.limiters[task];
+        match limiter.check() {
+            Ok(()) => {
+                state.running += 1;
+                Ok(Permit { admission: self })
+            }
+            Err(not_until) => Err(Some(not_until.wait_time_from(limiter.clock().now()))),
+        }
+    }
+}
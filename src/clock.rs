@@ -0,0 +1,144 @@
+// where an executor reads the time from, its ratelimiters, its waits, its event timestamps and the
+// simulated tasks it runs all use the same clock
+//
+// `TokioClock` is the default and follows tokio's virtual time when it is paused, `SystemClock`
+// reads the monotonic clock of the system whatever tokio does, and `ManualClock` only moves when
+// told to
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use tokio::sync::watch;
+
+// times are offsets from an origin each clock picks, usually when it was created
+#[async_trait]
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Duration;
+
+    async fn sleep_until(&self, deadline: Duration);
+
+    async fn sleep(&self, duration: Duration) {
+        self.sleep_until(self.now() + duration).await
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TokioClock {
+    origin: tokio::time::Instant,
+}
+
+impl TokioClock {
+    pub fn new() -> Self {
+        Self { origin: tokio::time::Instant::now() }
+    }
+}
+
+impl Default for TokioClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Clock for TokioClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    async fn sleep_until(&self, deadline: Duration) {
+        tokio::time::sleep_until(self.origin + deadline).await
+    }
+}
+
+// waits still go through tokio timers, don't pause tokio's clock with this one
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    origin: std::time::Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self { origin: std::time::Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    async fn sleep_until(&self, deadline: Duration) {
+        while self.now() < deadline {
+            tokio::time::sleep(deadline - self.now()).await;
+        }
+    }
+}
+
+// starts at zero and stays there until `advance` or `set` is called, clones share the same time
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<watch::Sender<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self { now: Arc::new(watch::channel(Duration::ZERO).0) }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.send_modify(|now| *now += by);
+    }
+
+    // time never goes back, setting an earlier time does nothing
+    pub fn set(&self, to: Duration) {
+        self.now.send_if_modified(|now| {
+            let later = to > *now;
+            *now = (*now).max(to);
+            later
+        });
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.now.borrow()
+    }
+
+    async fn sleep_until(&self, deadline: Duration) {
+        let mut now = self.now.subscribe();
+        // the sender lives as long as self
+        let _ = now.wait_for(|&now| now >= deadline).await;
+    }
+}
+
+tokio::task_local! {
+    static CURRENT: Arc<dyn Clock>;
+}
+
+// sleeps on the clock of the executor running the task, or on tokio's outside of an executor,
+// handlers should use it instead of `tokio::time::sleep` to follow the executor's time
+pub async fn sleep(duration: Duration) {
+    match CURRENT.try_with(Arc::clone) {
+        Ok(clock) => clock.sleep(duration).await,
+        Err(_) => tokio::time::sleep(duration).await,
+    }
+}
+
+// runs a handler with `clock` as the current clock
+pub(crate) async fn scope<F: Future>(clock: Arc<dyn Clock>, future: F) -> F::Output {
+    CURRENT.scope(clock, future).await
+}
@@ -0,0 +1,247 @@
+// household configuration, the robots, the task types and the parallelism, in toml or json
+//
+// concurrency = 3
+// robots = ["Dave", "Cris"]
+// aging = 10.0 # seconds a job behind higher priorities waits for its own to go up by one, 10 by default
+// on_failure = { Dave = "halt" } # or "skip_dependents" or "continue" (the default)
+// capabilities = { Nick = ["clean_the_windows"] } # the task types a robot is limited to, every one otherwise
+//
+// [tasks.clean_the_windows]
+// duration = 0.3 # simulated execution time in seconds
+// output = "Squeeesh"
+// interval = 5.0 # seconds between two starts
+//
+// [tasks.feed_the_cat]
+// duration = 0.5
+// quota = { count = 3, period = 60.0 } # 3 starts per minute, bursts up to 3 unless `burst` is set
+// retry = { attempts = 3, backoff = 0.5, max_backoff = 10.0, multiplier = 2.0, jitter = 0.5 }
+// timeout = 5.0 # seconds before a stuck run is cancelled
+// on_timeout = "skip" # or "continue" (the default) or "halt"
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+use serde::Deserialize;
+use crate::schedule::TaskSpec;
+use crate::policy::{OnFailure, OnTimeout};
+use crate::retry::RetryPolicy;
+use crate::task::{Rate, Registry, Simulated};
+use crate::{Error, ExecutorBuilder};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Quota {
+    pub count: u32,
+    pub period: f64, // seconds
+}
+
+// every error is retried, only the attempts are required
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetryConfig {
+    pub attempts: u32, // counting the first one
+    pub backoff: Option<f64>, // seconds before the first retry
+    pub max_backoff: Option<f64>,
+    pub multiplier: Option<f64>,
+    pub jitter: Option<f64>,
+}
+
+impl RetryConfig {
+    fn policy(&self) -> Result<RetryPolicy, String> {
+        let backoff = |value: f64, what: &str| seconds(value, false).ok_or_else(|| format!("{what} must be a positive number of seconds, got {value}"));
+        let mut policy = RetryPolicy::attempts(self.attempts);
+        if self.backoff.is_some() || self.max_backoff.is_some() {
+            let initial = self.backoff.map_or(Ok(policy.initial), |b| backoff(b, "backoff"))?;
+            let max = self.max_backoff.map_or(Ok(policy.max.max(initial)), |b| backoff(b, "max_backoff"))?;
+            policy = policy.backoff(initial, max);
+        }
+        if let Some(multiplier) = self.multiplier {
+            policy = policy.multiplier(multiplier);
+        }
+        if let Some(jitter) = self.jitter {
+            policy = policy.jitter(jitter);
+        }
+        policy.check()?;
+        Ok(policy)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskConfig {
+    pub duration: f64, // seconds
+    pub output: Option<String>, // defaults to the task name
+    pub interval: Option<f64>, // seconds, exclusive with `quota`
+    pub quota: Option<Quota>,
+    pub burst: Option<u32>,
+    pub retry: Option<RetryConfig>,
+    pub timeout: Option<f64>, // seconds
+    pub on_timeout: Option<OnTimeout>,
+}
+
+// seconds that fit in a `Duration`, and aren't zero once converted if `positive`, so converting
+// them later can't panic
+fn seconds(value: f64, positive: bool) -> Option<Duration> {
+    Duration::try_from_secs_f64(value).ok().filter(|duration| !(positive && duration.is_zero()))
+}
+
+impl TaskConfig {
+    fn check(&self, name: &str) -> Result<(), String> {
+        if seconds(self.duration, false).is_none() {
+            return Err(format!("task {name} : duration must be a positive number of seconds, got {}", self.duration));
+        }
+        match (self.interval, &self.quota) {
+            (Some(_), Some(_)) => return Err(format!("task {name} : set either interval or quota, not both")),
+            (None, None) => return Err(format!("task {name} : needs an interval or a quota")),
+            (Some(interval), None) if seconds(interval, true).is_none() => {
+                return Err(format!("task {name} : interval must be a number of seconds greater than zero, got {interval}"));
+            }
+            (None, Some(quota)) if quota.count == 0 || seconds(quota.period, true).is_none() => {
+                return Err(format!("task {name} : quota needs a count and a period greater than zero"));
+            }
+            (None, Some(quota)) if seconds(quota.period, true).is_some_and(|period| (period / quota.count).is_zero()) => {
+                return Err(format!("task {name} : quota of {} per {}s is too many starts to ratelimit", quota.count, quota.period));
+            }
+            _ => {}
+        }
+        if self.burst == Some(0) {
+            return Err(format!("task {name} : burst must be at least 1"));
+        }
+        if let Some(retry) = &self.retry {
+            retry.policy().map_err(|e| format!("task {name} : retry {e}"))?;
+        }
+        match self.timeout {
+            Some(timeout) if seconds(timeout, true).is_none() => {
+                return Err(format!("task {name} : timeout must be a number of seconds greater than zero, got {timeout}"));
+            }
+            None if self.on_timeout.is_some() => return Err(format!("task {name} : on_timeout needs a timeout")),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn rate(&self) -> Rate {
+        match (self.interval, &self.quota) {
+            (Some(interval), _) => Rate::every(Duration::from_secs_f64(interval)).burst(self.burst.unwrap_or(1)),
+            (None, Some(quota)) => Rate::every(Duration::from_secs_f64(quota.period) / quota.count)
+                .burst(self.burst.unwrap_or(quota.count)),
+            (None, None) => unreachable!("checked by validate"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub concurrency: usize,
+    pub robots: Vec<String>,
+    #[serde(default)]
+    pub on_failure: BTreeMap<String, OnFailure>, // per robot
+    #[serde(default)]
+    pub capabilities: BTreeMap<String, Vec<String>>, // per robot
+    pub aging: Option<f64>, // seconds
+    pub tasks: BTreeMap<String, TaskConfig>,
+}
+
+impl Config {
+    // `.json` files are json, anything else is toml
+    pub fn read(path: &str) -> Result<Self, Error> {
+        let input = std::fs::read_to_string(path).map_err(|e| Error::Io(format!("{path} : {e}")))?;
+        if path.ends_with(".json") {
+            Self::from_json(&input)
+        } else {
+            Self::from_toml(&input)
+        }
+    }
+
+    pub fn from_toml(input: &str) -> Result<Self, Error> {
+        let config: Self = toml::from_str(input).map_err(|e| Error::Config(e.to_string().trim_end().to_owned()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_json(input: &str) -> Result<Self, Error> {
+        let config: Self = serde_json::from_str(input).map_err(|e| Error::Config(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.concurrency == 0 {
+            return Err(Error::Config("concurrency must be at least 1".into()));
+        }
+        if let Some(aging) = self.aging.filter(|&aging| seconds(aging, true).is_none()) {
+            return Err(Error::Config(format!("aging must be a number of seconds greater than zero, got {aging}")));
+        }
+        if self.robots.is_empty() {
+            return Err(Error::Config("at least one robot is needed".into()));
+        }
+        let mut names = HashSet::new();
+        for robot in &self.robots {
+            if robot.trim().is_empty() {
+                return Err(Error::Config("robot names can't be empty".into()));
+            }
+            if !names.insert(robot) {
+                return Err(Error::Config(format!("robot {robot} is declared twice")));
+            }
+        }
+        if let Some(robot) = self.on_failure.keys().find(|robot| !names.contains(robot)) {
+            return Err(Error::Config(format!("on_failure is set for robot {robot} which isn't declared")));
+        }
+        if let Some(robot) = self.capabilities.keys().find(|robot| !names.contains(robot)) {
+            return Err(Error::Config(format!("capabilities are set for robot {robot} which isn't declared")));
+        }
+        if self.tasks.is_empty() {
+            return Err(Error::Config("at least one task type is needed".into()));
+        }
+        for (robot, tasks) in &self.capabilities {
+            if let Some(task) = tasks.iter().find(|task| !self.tasks.contains_key(*task)) {
+                return Err(Error::Config(format!("robot {robot} is capable of {task} which isn't declared")));
+            }
+        }
+        for (name, task) in &self.tasks {
+            task.check(name).map_err(Error::Config)?;
+        }
+        Ok(())
+    }
+
+    // every task type as a simulated task taking its configured duration
+    pub fn registry(&self) -> Registry {
+        let mut registry = Registry::new();
+        for (name, task) in &self.tasks {
+            let output = task.output.clone().unwrap_or_else(|| name.clone());
+            registry.register(name, task.rate(), Simulated { duration: Duration::from_secs_f64(task.duration), output });
+        }
+        registry
+    }
+
+    pub fn specs(&self) -> HashMap<String, TaskSpec> {
+        self.tasks.iter()
+            .map(|(name, task)| {
+                let rate = task.rate();
+                (name.clone(), TaskSpec { duration: Duration::from_secs_f64(task.duration), interval: rate.interval, burst: rate.burst })
+            })
+            .collect()
+    }
+
+    // an executor builder with the robots, task types and concurrency of this config
+    pub fn builder(&self) -> ExecutorBuilder {
+        let builder = ExecutorBuilder::default()
+            .robots(&self.robots)
+            .registry(self.registry())
+            .concurrency(self.concurrency);
+        let builder = match self.aging {
+            Some(aging) => builder.aging(Duration::from_secs_f64(aging)),
+            None => builder,
+        };
+        let builder = self.on_failure.iter().fold(builder, |builder, (robot, &policy)| builder.on_failure(robot, policy));
+        let builder = self.capabilities.iter().fold(builder, |builder, (robot, tasks)| builder.robot_capable_of(robot, tasks));
+        self.tasks.iter().fold(builder, |mut builder, (name, task)| {
+            if let Some(retry) = &task.retry {
+                builder = builder.retry(name, retry.policy().expect("checked by validate"));
+            }
+            if let Some(timeout) = task.timeout {
+                builder = builder.timeout(name, Duration::from_secs_f64(timeout), task.on_timeout.unwrap_or_default());
+            }
+            builder
+        })
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    UnknownRobot(String),
+    DuplicateRobot(String), // added to a session it already works in
+    Incapable { robot: String, task: String }, // the robot doesn't do this task type
+    UnknownTask(String),
+    DuplicateId(usize),
+    Closed, // the session doesn't accept jobs anymore
+    Parse { line: usize, message: String },
+    Io(String),
+    InvalidRate(String), // a zero interval or burst can't be ratelimited
+    Config(String),
+    InvalidConcurrency, // a concurrency of 0 would never run anything
+    InvalidRetry { task: String, message: String },
+    InvalidTimeout(String), // a zero timeout would cancel every task
+    Dependency { id: usize, message: String },
+    InvalidAging, // a zero aging would raise every priority to the top at once
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownRobot(robot) => write!(f, "unknown robot {robot}"),
+            Error::DuplicateRobot(robot) => write!(f, "robot {robot} is already working"),
+            Error::Incapable { robot, task } => write!(f, "robot {robot} can't do {task}"),
+            Error::UnknownTask(task) => write!(f, "invalid task name : {task}"),
+            Error::DuplicateId(id) => write!(f, "duplicate task id {id}"),
+            Error::Closed => write!(f, "the executor is closed"),
+            Error::Parse { line, message } => write!(f, "line {line} : {message}"),
+            Error::Io(message) => write!(f, "{message}"),
+            Error::InvalidRate(task) => write!(f, "task {task} needs an interval and a burst greater than zero"),
+            Error::Config(message) => write!(f, "invalid config : {message}"),
+            Error::InvalidConcurrency => write!(f, "concurrency must be at least 1"),
+            Error::InvalidRetry { task, message } => write!(f, "invalid retry policy for task {task} : {message}"),
+            Error::InvalidTimeout(task) => write!(f, "task {task} needs a timeout greater than zero"),
+            Error::Dependency { id, message } => write!(f, "id {id} {message}"),
+            Error::InvalidAging => write!(f, "aging must be greater than zero"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,127 @@
+// progress of the executor as typed events, timestamps are offsets from the creation of the
+// executor
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Queued, // handed to the robot
+    WaitingRateLimit,
+    WaitingSlot, // waiting for one of the concurrency permits
+    Started,
+    Retrying, // the attempt failed, the task runs again after a backoff
+    Finished,
+    Failed,
+    TimedOut, // cancelled after running longer than the timeout of its task type
+    Skipped, // never started because of an earlier task, the reason is in `error`
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ExecutorEvent {
+    Task {
+        #[serde(with = "secs")]
+        at: Duration,
+        robot: String,
+        id: usize,
+        task: String,
+        phase: Phase,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        output: Option<String>, // only for finished tasks
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>, // only for failed, retried, timed out and skipped tasks
+    },
+    RobotStopped {
+        #[serde(with = "secs")]
+        at: Duration,
+        robot: String,
+    },
+    Done { // the session is closed and every task has been run
+        #[serde(with = "secs")]
+        at: Duration,
+    },
+}
+
+impl ExecutorEvent {
+    pub fn at(&self) -> Duration {
+        match self {
+            ExecutorEvent::Task { at, .. } | ExecutorEvent::RobotStopped { at, .. } | ExecutorEvent::Done { at } => *at,
+        }
+    }
+}
+
+// durations as a number of seconds, easier to read and to parse from other tools
+pub(crate) mod secs {
+    use std::time::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(at: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(at.as_secs_f64())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let secs = f64::deserialize(deserializer)?;
+        Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom)
+    }
+
+    pub mod option {
+        use std::time::Duration;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(at: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+            match at {
+                Some(at) => serializer.serialize_some(&at.as_secs_f64()),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+            Option::<f64>::deserialize(deserializer)?
+                .map(|secs| Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom))
+                .transpose()
+        }
+    }
+}
+
+// called synchronously by the executor for every event, keep it fast
+pub trait Subscriber: Send + Sync {
+    fn event(&self, event: &ExecutorEvent);
+}
+
+impl<F: Fn(&ExecutorEvent) + Send + Sync> Subscriber for F {
+    fn event(&self, event: &ExecutorEvent) {
+        self(event)
+    }
+}
+
+// the default, one line of text per step a robot goes through
+pub struct Printer;
+
+impl Subscriber for Printer {
+    fn event(&self, event: &ExecutorEvent) {
+        match event {
+            ExecutorEvent::Task { robot, id, task, phase, error, .. } => match phase {
+                Phase::WaitingRateLimit => println!("{robot} waiting for {task} with id {id}"),
+                Phase::Started => println!("{robot} started {task} with id {id}"),
+                Phase::Finished => println!("{robot} finished {task} with id {id}"),
+                Phase::Failed => println!("{robot} failed {task} with id {id} : {}", error.as_deref().unwrap_or("unknown error")),
+                Phase::Retrying => println!("{robot} failed {task} with id {id} : {}, retrying", error.as_deref().unwrap_or("unknown error")),
+                Phase::TimedOut => println!("{robot} {} on {task} with id {id}", error.as_deref().unwrap_or("timed out")),
+                Phase::Skipped => println!("{robot} skipped {task} with id {id} : {}", error.as_deref().unwrap_or("skipped")),
+                Phase::Queued | Phase::WaitingSlot => {}
+            },
+            ExecutorEvent::RobotStopped { robot, .. } => println!("robot : {robot} finished working"),
+            ExecutorEvent::Done { .. } => println!("all tasks have been done"),
+        }
+    }
+}
+
+// every event as a json object on its own line
+pub struct JsonLinesPrinter;
+
+impl Subscriber for JsonLinesPrinter {
+    fn event(&self, event: &ExecutorEvent) {
+        println!("{}", serde_json::to_string(event).expect("events always serialize"));
+    }
+}
@@ -0,0 +1,259 @@
+// exact solver, a depth first branch and bound over the interleavings of the robot queues
+//
+// tasks are placed one at a time in order of start time, each at the earliest instant allowed by
+// its robot, the jobs it depends on, its ratelimiter and the concurrency limit, any schedule can be left shifted into one
+// of those so exploring all of them is enough to find an optimal one
+//
+// the search has to beat the schedule of the optimized planner, which is what comes back if the
+// budget runs out before it finds a shorter one
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use crate::graph;
+use crate::job::DEFAULT_AGING;
+use crate::{Error, Job, Strategy, simulate};
+use crate::schedule::{Entry, Schedule, TaskSpec};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Budget {
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self { nodes: Some(50_000_000), time: Some(Duration::from_secs(30)) }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub schedule: Schedule,
+    pub optimal: bool, // false if the budget ran out before the search could prove it
+    pub lower_bound: Duration, // no schedule can be shorter than this
+    pub nodes: u64,
+}
+
+struct Search<'a> {
+    names: Vec<&'a str>,
+    queues: Vec<Vec<(usize, usize)>>, // per robot (task id, task type)
+    types: Vec<(&'a str, TaskSpec)>,
+    after: HashMap<usize, Vec<usize>>, // the jobs each job depends on, if any
+
+    pos: Vec<usize>,
+    robot_free: Vec<Duration>,
+    robot_work: Vec<Duration>, // execution time left per robot
+    type_tat: Vec<Duration>, // theoretical arrival time of each ratelimiter
+    type_left: Vec<u32>,
+    slots: Vec<Duration>, // end of the tasks holding the concurrency slots
+    last_start: Duration,
+    end: Duration,
+    path: Vec<(usize, Duration)>, // (robot, start) in placement order
+    ends: HashMap<usize, Duration>, // of the tasks placed so far
+
+    best: (Duration, Vec<(usize, Duration)>), // makespan and path of the best schedule so far
+    root_bound: Duration,
+    nodes: u64,
+    budget: Budget,
+    started: Instant,
+    exhausted: bool,
+}
+
+impl Search<'_> {
+    fn out_of_budget(&mut self) -> bool {
+        if self.budget.nodes.is_some_and(|n| self.nodes >= n) {
+            self.exhausted = true;
+        }
+        if self.nodes & 1023 == 0 && self.budget.time.is_some_and(|t| self.started.elapsed() >= t) {
+            self.exhausted = true;
+        }
+        self.exhausted
+    }
+
+    fn bound(&self) -> Duration {
+        let mut bound = self.end;
+        for (k, &(_, spec)) in self.types.iter().enumerate() {
+            if self.type_left[k] > 0 {
+                // the bucket lets the first `burst` starts through at once, the others one per interval
+                let tat = self.last_start.max(self.type_tat[k]) + spec.interval * (self.type_left[k] - 1);
+                bound = bound.max(self.last_start.max(spec.rate().available_at(tat)) + spec.duration);
+            }
+        }
+        for r in 0..self.queues.len() {
+            if !self.robot_work[r].is_zero() {
+                bound = bound.max(self.last_start.max(self.robot_free[r]) + self.robot_work[r]);
+            }
+        }
+        bound
+    }
+
+    // none once every job the robot's next task depends on is placed
+    fn earliest_start(&self, r: usize) -> Option<(Duration, usize)> {
+        let (id, k) = self.queues[r][self.pos[r]];
+        let mut ready = self.last_start.max(self.robot_free[r]);
+        for after in self.after.get(&id).into_iter().flatten() {
+            ready = ready.max(*self.ends.get(after)?);
+        }
+        let (slot, &slot_free) = self.slots.iter().enumerate().min_by_key(|&(_, t)| *t).expect("at least one slot");
+        let start = ready.max(self.types[k].1.rate().available_at(self.type_tat[k])).max(slot_free);
+        Some((start, slot))
+    }
+
+    // two robots with the same remaining tasks and free at the same time are interchangeable, as
+    // long as no job waits on another robot
+    fn symmetric(&self, a: usize, b: usize) -> bool {
+        self.after.is_empty() && self.robot_free[a] == self.robot_free[b] && self.queues[a][self.pos[a]..].iter().map(|t| t.1)
+            .eq(self.queues[b][self.pos[b]..].iter().map(|t| t.1))
+    }
+
+    fn dfs(&mut self) {
+        if self.out_of_budget() {
+            return;
+        }
+        self.nodes += 1;
+
+        if self.pos.iter().zip(&self.queues).all(|(&p, q)| p == q.len()) {
+            if self.end < self.best.0 {
+                self.best = (self.end, self.path.clone());
+            }
+            return;
+        }
+
+        let mut children: Vec<_> = (0..self.queues.len())
+            .filter(|&r| self.pos[r] < self.queues[r].len())
+            .filter(|&r| !(0..r).any(|o| self.pos[o] < self.queues[o].len() && self.symmetric(o, r)))
+            .filter_map(|r| {
+                let (start, slot) = self.earliest_start(r)?;
+                let (_, k) = self.queues[r][self.pos[r]];
+                let weight = self.types[k].1.interval * self.type_left[k];
+                Some((r, start, slot, weight))
+            })
+            .collect();
+        // try the most promising placements first so a good schedule is found early
+        children.sort_by_key(|&(r, start, _, weight)| (start, Reverse(weight), Reverse(self.robot_work[r]), r));
+
+        for (r, start, slot, _) in children {
+            let (id, k) = self.queues[r][self.pos[r]];
+            let spec = self.types[k].1;
+            let finish = start + spec.duration;
+
+            let saved = (self.robot_free[r], self.type_tat[k], self.slots[slot], self.last_start, self.end);
+            self.pos[r] += 1;
+            self.robot_free[r] = finish;
+            self.robot_work[r] -= spec.duration;
+            self.type_tat[k] = spec.rate().started(self.type_tat[k], start);
+            self.type_left[k] -= 1;
+            self.slots[slot] = finish;
+            self.last_start = start;
+            self.end = self.end.max(finish);
+            self.path.push((r, start));
+            self.ends.insert(id, finish);
+
+            if self.bound() < self.best.0 {
+                self.dfs();
+            }
+
+            self.path.pop();
+            self.ends.remove(&id);
+            self.pos[r] -= 1;
+            (self.robot_free[r], self.type_tat[k], self.slots[slot], self.last_start, self.end) = saved;
+            self.robot_work[r] += spec.duration;
+            self.type_left[k] += 1;
+
+            let proven = self.best.0 <= self.root_bound;
+            if proven || self.exhausted {
+                return;
+            }
+        }
+    }
+}
+
+pub fn solve(jobs: &[Job], specs: &HashMap<String, TaskSpec>, concurrency: usize, budget: Budget) -> Result<Solution, Error> {
+    if concurrency == 0 {
+        return Err(Error::InvalidConcurrency);
+    }
+    graph::check(jobs)?;
+
+    let mut names: Vec<&str> = Vec::new();
+    let mut queues: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut types: Vec<(&str, TaskSpec)> = Vec::new();
+    for Job { id, robot, task, .. } in jobs {
+        let r = names.iter().position(|n| n == robot).unwrap_or_else(|| {
+            names.push(robot);
+            queues.push(Vec::new());
+            names.len() - 1
+        });
+        let k = match types.iter().position(|(n, _)| n == task) {
+            Some(k) => k,
+            None => {
+                let spec = *specs.get(task).ok_or_else(|| Error::UnknownTask(task.clone()))?;
+                types.push((task, spec));
+                types.len() - 1
+            }
+        };
+        queues[r].push((*id, k));
+    }
+
+    let after = jobs.iter().filter(|job| !job.after.is_empty()).map(|job| (job.id, job.after.clone())).collect();
+
+    let robot_work = queues.iter().map(|q| q.iter().map(|&(_, k)| types[k].1.duration).sum()).collect();
+    let mut type_left = vec![0; types.len()];
+    for &(_, k) in queues.iter().flatten() {
+        type_left[k] += 1;
+    }
+
+    // the optimized planner's schedule is the one to beat, and what is left if the budget runs out
+    let planned = simulate::simulate(jobs, specs, concurrency, DEFAULT_AGING, Strategy::Optimized)?;
+    let queued: HashMap<usize, (usize, usize)> = queues.iter().enumerate()
+        .flat_map(|(r, queue)| queue.iter().enumerate().map(move |(i, &(id, _))| (id, (r, i))))
+        .collect();
+    let mut placed: Vec<(Duration, usize, usize)> = planned.entries.iter()
+        .map(|e| {
+            let (r, i) = queued[&e.id];
+            (e.start, i, r)
+        })
+        .collect();
+    placed.sort();
+
+    let mut search = Search {
+        pos: vec![0; names.len()],
+        robot_free: vec![Duration::ZERO; names.len()],
+        robot_work,
+        type_tat: vec![Duration::ZERO; types.len()],
+        type_left,
+        slots: vec![Duration::ZERO; concurrency],
+        last_start: Duration::ZERO,
+        end: Duration::ZERO,
+        path: Vec::with_capacity(jobs.len()),
+        ends: HashMap::new(),
+        best: (planned.makespan, placed.into_iter().map(|(start, _, r)| (r, start)).collect()),
+        root_bound: Duration::ZERO,
+        nodes: 0,
+        budget,
+        started: Instant::now(),
+        exhausted: false,
+        names,
+        queues,
+        types,
+        after,
+    };
+    search.root_bound = search.bound();
+    search.dfs();
+
+    // replay the best placement order to rebuild the entries
+    let mut pos = vec![0; search.queues.len()];
+    let entries = search.best.1.iter().map(|&(r, start)| {
+        let (id, k) = search.queues[r][pos[r]];
+        pos[r] += 1;
+        let (task, spec) = search.types[k];
+        Entry { id, robot: search.names[r].into(), task: task.into(), start, end: start + spec.duration }
+    }).collect();
+
+    let schedule = Schedule::new(entries);
+    Ok(Solution {
+        optimal: !search.exhausted || schedule.makespan <= search.root_bound,
+        lower_bound: search.root_bound,
+        nodes: search.nodes,
+        schedule,
+    })
+}
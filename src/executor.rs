@@ -0,0 +1,1049 @@
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::num::NonZeroU32;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::{FutureExt, StreamExt};
+use tokio::sync::{broadcast, oneshot, watch};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tokio::task::{JoinError, JoinHandle, JoinSet};
+use crate::admission::{Admission, Limiter, LimiterClock};
+use crate::clock::{self, Clock, TokioClock};
+use crate::verify::{self, Limits};
+use crate::event::{ExecutorEvent, Phase, Printer, Subscriber};
+use crate::graph::{self, Graph};
+use crate::optimized::{Planner, Step};
+use crate::report::{Attempt, Outcome, Report, TaskResult, Wait};
+use crate::policy::{Fallout, OnFailure, OnTimeout};
+use crate::retry::RetryPolicy;
+use crate::task::{Output, Rate, Registered, Registry, TaskError, TaskHandler};
+use crate::job::DEFAULT_AGING;
+use crate::{Error, Job, Strategy};
+
+fn ratelimiter_with_rate(rate: Rate, clock: LimiterClock) -> Option<Limiter> {
+    let quota = governor::Quota::with_period(rate.interval)?.allow_burst(NonZeroU32::new(rate.burst)?);
+    Some(governor::RateLimiter::direct_with_clock(quota, clock))
+}
+
+struct Task {
+    rate: Rate,
+    handler: Arc<dyn TaskHandler>,
+    retry: Option<RetryPolicy>,
+    timeout: Option<(Duration, OnTimeout)>,
+}
+
+enum Ran {
+    Done(Result<Output, TaskError>),
+    TimedOut(Duration),
+}
+
+impl Task {
+    // runs the handler on the executor's clock, dropping its future once it has run longer than
+    // the timeout, a handler that panics fails like one returning an error
+    async fn run(&self, clock: &Arc<dyn Clock>, job: &Job) -> Ran {
+        let run = AssertUnwindSafe(clock::scope(clock.clone(), self.handler.run(job.id, &job.robot)))
+            .catch_unwind()
+            .map(|ran| ran.unwrap_or_else(|panic| Err(panicked(panic))));
+        match self.timeout {
+            None => Ran::Done(run.await),
+            Some((limit, _)) => tokio::select! {
+                biased;
+                result = run => Ran::Done(result),
+                _ = clock.sleep(limit) => Ran::TimedOut(limit),
+            },
+        }
+    }
+
+    // none when the failed attempt is final
+    fn retry_after(&self, job: &Job, attempt: u32, error: &TaskError) -> Option<Duration> {
+        self.retry.as_ref()?.delay(job.id, attempt, error)
+    }
+}
+
+fn panicked(panic: Box<dyn Any + Send>) -> TaskError {
+    let message = match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => panic.downcast_ref::<&str>().map_or("the handler panicked", |message| message).to_owned(),
+    };
+    format!("panicked : {message}").into()
+}
+
+// what happens next once an attempt is over
+enum Next {
+    Retry(Duration), // after this backoff
+    Done(Option<String>), // the task is over, the jobs queued behind it are skipped for this reason if any
+}
+
+// how far along a session is in shutting down, only moves forward
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stop {
+    Run,
+    Drain, // nothing starts anymore, the running tasks finish
+    Abort, // the running tasks are dropped where they are
+}
+
+const DRAINED: &str = "shut down before it started";
+
+// what a retired robot does with the jobs it hasn't started
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retire {
+    Drain, // runs them first
+    Now, // hands them back, the task it is running still ends
+}
+
+// what a session's dispatcher is sent, in the order it was sent
+enum Command {
+    Job(Job),
+    Hire(String),
+    Retire { robot: String, when: Retire, done: oneshot::Sender<Vec<Job>> },
+}
+
+// resolves once the session is asked to stop at least this hard, never if every sender is gone
+// since it then can't be asked anymore
+async fn stopping(mut stop: watch::Receiver<Stop>, level: Stop) {
+    if stop.wait_for(|&s| s >= level).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+// resolves once the robot is retired on the spot, never if it is left to drain its queue
+async fn dismissal(mut dismissed: watch::Receiver<bool>) {
+    if dismissed.wait_for(|&d| d).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+struct Inner {
+    robots: Vec<String>,
+    tasks: HashMap<String, Task>,
+    on_failure: HashMap<String, OnFailure>, // continue for the robots not in there
+    capabilities: HashMap<String, HashSet<String>>, // the robots not in there do every task type
+    aging: Duration,
+    admission: Admission, // owns the ratelimiters and the concurrency slots
+    concurrency: usize,
+    strategy: Strategy,
+    clock: Arc<dyn Clock>,
+    subscribers: Vec<Arc<dyn Subscriber>>,
+    events: broadcast::Sender<ExecutorEvent>,
+}
+
+impl Inner {
+    fn emit(&self, event: ExecutorEvent) {
+        for subscriber in &self.subscribers {
+            subscriber.event(&event);
+        }
+        let _ = self.events.send(event); // fine if nobody listens
+    }
+
+    fn fallout(&self, robot: &str) -> Fallout {
+        Fallout::new(self.on_failure.get(robot).copied().unwrap_or_default())
+    }
+}
+
+struct Record {
+    result: TaskResult,
+    phase: Phase,
+    since: Duration, // when the task entered its current phase
+}
+
+impl Record {
+    fn enter(&mut self, phase: Phase, at: Duration) {
+        match self.phase {
+            Phase::WaitingRateLimit => self.result.rate_limit_wait += at - self.since,
+            Phase::WaitingSlot => self.result.slot_wait += at - self.since,
+            _ => {}
+        }
+        if matches!(self.phase, Phase::WaitingRateLimit | Phase::WaitingSlot) && at > self.since {
+            self.result.waits.push(Wait { phase: self.phase, start: self.since, end: at });
+        }
+        self.phase = phase;
+        self.since = at;
+    }
+}
+
+// state of one session, turns the steps of every task into events and into the final report
+struct Tracker {
+    inner: Arc<Inner>,
+    start: Duration, // on the executor's clock, event timestamps and deadlines are relative to it
+    records: Mutex<BTreeMap<usize, Record>>,
+    queued: AtomicUsize, // how many jobs were queued so far
+    ended: watch::Sender<bool>, // notified whenever a task ends, true once every job was received
+}
+
+impl Tracker {
+    fn new(inner: Arc<Inner>) -> Self {
+        Self { start: inner.clock.now(), inner, records: Mutex::new(BTreeMap::new()), queued: AtomicUsize::new(0), ended: watch::channel(false).0 }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.inner.clock.now().saturating_sub(self.start)
+    }
+
+    // no more jobs are coming, a dependency that was never received won't hold anything back anymore
+    fn seal(&self) {
+        self.ended.send_replace(true);
+    }
+
+    // the job was handed back and may be submitted again
+    fn forget(&self, job: &Job) {
+        self.records.lock().expect("tracker lock poisoned").remove(&job.id);
+    }
+
+    fn finished(&self, id: usize) -> bool {
+        let records = self.records.lock().expect("tracker lock poisoned");
+        records.get(&id).is_some_and(|record| matches!(record.result.outcome, Outcome::Finished { .. }))
+    }
+
+    // waits until every job this one depends on has ended, whatever its robot
+    async fn dependencies(&self, job: &Job) {
+        let mut ended = self.ended.subscribe();
+        loop {
+            let sealed = *ended.borrow_and_update();
+            let waiting = {
+                let records = self.records.lock().expect("tracker lock poisoned");
+                job.after.iter().any(|id| records.get(id).map_or(!sealed, |record| record.result.outcome == Outcome::Pending))
+            };
+            if !waiting {
+                return;
+            }
+            let _ = ended.changed().await; // the sender is ours, it can't be gone
+        }
+    }
+
+    fn emit(&self, job: &Job, at: Duration, phase: Phase, output: Option<String>, error: Option<String>) {
+        self.inner.emit(ExecutorEvent::Task {
+            at,
+            robot: job.robot.clone(),
+            id: job.id,
+            task: job.task.clone(),
+            phase,
+            output,
+            error,
+        });
+    }
+
+    // moving to the same phase again is a no op
+    fn phase(&self, job: &Job, phase: Phase) {
+        let at = self.elapsed();
+        {
+            let mut records = self.records.lock().expect("tracker lock poisoned");
+            let record = records.entry(job.id).or_insert_with(|| Record {
+                result: TaskResult {
+                    robot: job.robot.clone(),
+                    task: job.task.clone(),
+                    outcome: Outcome::Pending,
+                    queued: at,
+                    sequence: self.queued.fetch_add(1, Ordering::Relaxed),
+                    start: None,
+                    end: None,
+                    rate_limit_wait: Duration::ZERO,
+                    slot_wait: Duration::ZERO,
+                    deadline: job.deadline,
+                    waits: Vec::new(),
+                    retries: Vec::new(),
+                },
+                phase,
+                since: at,
+            });
+            if record.phase == phase && phase != Phase::Queued {
+                return;
+            }
+            record.enter(phase, at);
+            if phase == Phase::Started {
+                record.result.start = Some(at);
+            }
+        }
+        self.emit(job, at, phase, None, None);
+    }
+
+    // the attempt failed and the task will be started again
+    fn retry(&self, job: &Job, error: &TaskError) {
+        let at = self.elapsed();
+        let error = error.to_string();
+        if let Some(record) = self.records.lock().expect("tracker lock poisoned").get_mut(&job.id) {
+            let start = record.result.start.take().unwrap_or(at);
+            record.result.retries.push(Attempt { start, end: at, error: error.clone() });
+            record.enter(Phase::Retrying, at);
+        }
+        self.emit(job, at, Phase::Retrying, None, Some(error));
+    }
+
+    // records how the attempt ended, retrying or closing the task
+    fn settle(&self, job: &Job, attempt: u32, ran: Ran, fallout: &mut Fallout) -> Next {
+        let task_type = &self.inner.tasks[&job.task];
+        let outcome = match ran {
+            Ran::TimedOut(after) => Outcome::TimedOut { after },
+            Ran::Done(Ok(output)) => Outcome::Finished { output },
+            Ran::Done(Err(error)) => {
+                if let Some(delay) = task_type.retry_after(job, attempt, &error) {
+                    self.retry(job, &error);
+                    return Next::Retry(delay);
+                }
+                Outcome::Failed { error: error.to_string() }
+            }
+        };
+        let skip_queued = fallout.ended(job, &outcome, task_type.timeout.map(|(_, then)| then).unwrap_or_default());
+        self.close(job, outcome);
+        Next::Done(skip_queued)
+    }
+
+    fn skip(&self, job: &Job, reason: String) {
+        self.close(job, Outcome::Skipped { reason });
+    }
+
+    // the final outcome of a task, a skipped task never started so it has no end
+    fn close(&self, job: &Job, outcome: Outcome) {
+        let at = self.elapsed();
+        let (phase, output, error) = match &outcome {
+            Outcome::Finished { output } => (Phase::Finished, Some(output.clone()), None),
+            Outcome::Failed { error } => (Phase::Failed, None, Some(error.clone())),
+            Outcome::TimedOut { after } => (Phase::TimedOut, None, Some(format!("timed out after {:.3}s", after.as_secs_f64()))),
+            Outcome::Skipped { reason } => (Phase::Skipped, None, Some(reason.clone())),
+            Outcome::Pending => unreachable!("a task is closed with its final outcome"),
+        };
+        if let Some(record) = self.records.lock().expect("tracker lock poisoned").get_mut(&job.id) {
+            record.enter(phase, at);
+            if phase != Phase::Skipped {
+                record.result.end = Some(at);
+            }
+            record.result.outcome = outcome;
+        }
+        self.ended.send_modify(|_| {});
+        self.emit(job, at, phase, output, error);
+    }
+
+    fn report(&self) -> Report {
+        let records = self.records.lock().expect("tracker lock poisoned");
+        Report { tasks: records.iter().map(|(&id, record)| (id, record.result.clone())).collect() }
+    }
+}
+
+#[derive(Default)]
+pub struct ExecutorBuilder {
+    robots: Vec<String>,
+    registry: Registry,
+    concurrency: Option<usize>,
+    strategy: Strategy,
+    subscribers: Vec<Arc<dyn Subscriber>>,
+    quiet: bool,
+    clock: Option<Arc<dyn Clock>>,
+    retries: HashMap<String, RetryPolicy>,
+    timeouts: HashMap<String, (Duration, OnTimeout)>,
+    on_failure: HashMap<String, OnFailure>,
+    capabilities: HashMap<String, HashSet<String>>,
+    aging: Option<Duration>,
+}
+
+impl ExecutorBuilder {
+    pub fn robot(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        if !self.robots.contains(&name) {
+            self.robots.push(name);
+        }
+        self
+    }
+
+    pub fn robots<I: IntoIterator<Item = S>, S: Into<String>>(self, names: I) -> Self {
+        names.into_iter().fold(self, Self::robot)
+    }
+
+    // a robot that only does these task types, the others do every one
+    pub fn robot_capable_of<I: IntoIterator<Item = S>, S: Into<String>>(self, name: impl Into<String>, tasks: I) -> Self {
+        let name = name.into();
+        let mut builder = self.robot(name.clone());
+        builder.capabilities.insert(name, tasks.into_iter().map(Into::into).collect());
+        builder
+    }
+
+    pub fn task(mut self, name: impl Into<String>, rate: impl Into<Rate>, handler: impl TaskHandler + 'static) -> Self {
+        self.registry.register(name, rate, handler);
+        self
+    }
+
+    // adds every task type of the registry, replacing the ones with the same name
+    pub fn registry(mut self, registry: Registry) -> Self {
+        self.registry.merge(registry);
+        self
+    }
+
+    // how many tasks may run at once across all robots, defaults to one per robot
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    // replaces the default printer, can be called several times to add more subscribers
+    pub fn subscriber(mut self, subscriber: impl Subscriber + 'static) -> Self {
+        self.subscribers.push(Arc::new(subscriber));
+        self
+    }
+
+    // no default printer, events are still available through `Executor::subscribe`
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    // failed tasks of this type are retried, none are by default
+    pub fn retry(mut self, task: impl Into<String>, policy: RetryPolicy) -> Self {
+        self.retries.insert(task.into(), policy);
+        self
+    }
+
+    // a task of this type running longer than `limit` is cancelled, its robot then does `then`
+    pub fn timeout(mut self, task: impl Into<String>, limit: Duration, then: OnTimeout) -> Self {
+        self.timeouts.insert(task.into(), (limit, then));
+        self
+    }
+
+    // what the robot does with its next jobs once one of its tasks failed for good
+    pub fn on_failure(mut self, robot: impl Into<String>, policy: OnFailure) -> Self {
+        self.on_failure.insert(robot.into(), policy);
+        self
+    }
+
+    // how long a job waiting behind higher priority ones waits for its priority to be raised by one
+    // level, with no cap so it can end up ahead of them, defaults to `DEFAULT_AGING`
+    pub fn aging(mut self, aging: Duration) -> Self {
+        self.aging = Some(aging);
+        self
+    }
+
+    // defaults to a `TokioClock` started when the executor is built
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    pub fn build(mut self) -> Result<Executor, Error> {
+        let concurrency = self.concurrency.unwrap_or(self.robots.len().max(1));
+        if concurrency == 0 {
+            return Err(Error::InvalidConcurrency);
+        }
+        let aging = self.aging.unwrap_or(DEFAULT_AGING);
+        if aging.is_zero() {
+            return Err(Error::InvalidAging);
+        }
+
+        let clock = self.clock.unwrap_or_else(|| Arc::new(TokioClock::new()));
+        let mut tasks = HashMap::new();
+        let mut limiters = HashMap::new();
+        for (name, Registered { handler, rate }) in self.registry.into_inner() {
+            let limiter = ratelimiter_with_rate(rate, LimiterClock(clock.clone())).ok_or_else(|| Error::InvalidRate(name.clone()))?;
+            limiters.insert(name.clone(), limiter);
+            let retry = self.retries.remove(&name);
+            if let Some(policy) = &retry {
+                policy.check().map_err(|message| Error::InvalidRetry { task: name.clone(), message })?;
+            }
+            let timeout = self.timeouts.remove(&name);
+            if timeout.is_some_and(|(limit, _)| limit.is_zero()) {
+                return Err(Error::InvalidTimeout(name));
+            }
+            tasks.insert(name, Task { rate, handler, retry, timeout });
+        }
+        if let Some(task) = self.retries.into_keys().chain(self.timeouts.into_keys()).next() {
+            return Err(Error::UnknownTask(task));
+        }
+        if let Some(robot) = self.on_failure.keys().find(|robot| !self.robots.contains(robot)) {
+            return Err(Error::UnknownRobot(robot.clone()));
+        }
+        if let Some(task) = self.capabilities.values().flatten().find(|task| !tasks.contains_key(*task)) {
+            return Err(Error::UnknownTask(task.clone()));
+        }
+
+        if self.subscribers.is_empty() && !self.quiet {
+            self.subscribers.push(Arc::new(Printer));
+        }
+
+        Ok(Executor {
+            inner: Arc::new(Inner {
+                robots: self.robots,
+                tasks,
+                on_failure: self.on_failure,
+                capabilities: self.capabilities,
+                aging,
+                admission: Admission::new(concurrency, limiters, clock.clone(), aging),
+                concurrency,
+                strategy: self.strategy,
+                clock,
+                subscribers: self.subscribers,
+                events: broadcast::channel(1024).0,
+            }),
+        })
+    }
+}
+
+// the ratelimiters and the concurrency limit are shared by every run of the same executor, and by
+// its clones
+#[derive(Clone)]
+pub struct Executor {
+    inner: Arc<Inner>,
+}
+
+impl Executor {
+    pub fn builder() -> ExecutorBuilder {
+        ExecutorBuilder::default()
+    }
+
+    fn check(&self, job: &Job) -> Result<(), Error> {
+        if !self.inner.robots.contains(&job.robot) {
+            return Err(Error::UnknownRobot(job.robot.clone()));
+        }
+        self.check_task(job, self.inner.capabilities.get(&job.robot))
+    }
+
+    // the task type exists and the robot does it, with `capabilities` the task types it is limited to
+    fn check_task(&self, job: &Job, capabilities: Option<&HashSet<String>>) -> Result<(), Error> {
+        if !self.inner.tasks.contains_key(&job.task) {
+            return Err(Error::UnknownTask(job.task.clone()));
+        }
+        if capabilities.is_some_and(|tasks| !tasks.contains(&job.task)) {
+            return Err(Error::Incapable { robot: job.robot.clone(), task: job.task.clone() });
+        }
+        Ok(())
+    }
+
+    // checks that every job can be run by this executor, `run` does it before starting anything
+    pub fn validate(&self, jobs: &[Job]) -> Result<(), Error> {
+        for job in jobs {
+            self.check(job)?;
+        }
+        graph::check(jobs)
+    }
+
+    // runs a fixed list of jobs to completion
+    pub async fn run<J: Into<Job>>(&self, jobs: impl IntoIterator<Item = J>) -> Result<Report, Error> {
+        let jobs: Vec<Job> = jobs.into_iter().map(Into::into).collect();
+        self.validate(&jobs)?;
+
+        let session = self.start();
+        for job in jobs {
+            session.submit(job)?;
+        }
+        session.close();
+        Ok(session.join().await)
+    }
+
+    // the constraints every run of this executor must obey, to check a recorded run with `verify`
+    pub fn limits(&self) -> Limits {
+        Limits {
+            concurrency: self.inner.concurrency,
+            rates: self.inner.tasks.iter().map(|(name, task)| (name.clone(), task.rate)).collect(),
+            tolerance: verify::DEFAULT_TOLERANCE,
+        }
+    }
+
+    // every event from now on, a receiver that lags behind by more than 1024 events misses some
+    pub fn subscribe(&self) -> broadcast::Receiver<ExecutorEvent> {
+        self.inner.events.subscribe()
+    }
+
+    // starts a long running session that accepts jobs until it is closed, must be called from
+    // within a tokio runtime
+    pub fn start(&self) -> Session {
+        let (tx, rx) = unbounded_channel::<Command>();
+        let (stop, stopped) = watch::channel(Stop::Run);
+        let tracker = Arc::new(Tracker::new(self.inner.clone()));
+        let done = match self.inner.strategy {
+            Strategy::Idiomatic => tokio::task::spawn(run_idiomatic(tracker, rx, stopped)),
+            Strategy::Optimized => tokio::task::spawn(run_optimized(tracker, rx, stopped)),
+        };
+        let sender = JobSender {
+            executor: self.clone(),
+            state: Arc::new(Mutex::new(SenderState {
+                tx: Some(tx),
+                graph: Graph::default(),
+                robots: self.inner.robots.iter().map(|robot| (robot.clone(), self.inner.capabilities.get(robot).cloned())).collect(),
+                retiring: HashSet::new(),
+            })),
+            stop: Arc::new(stop),
+        };
+        Session { sender, done }
+    }
+}
+
+struct SenderState {
+    tx: Option<UnboundedSender<Command>>, // none once the session is closed
+    graph: Graph, // of every job submitted so far, a job may depend on one submitted later
+    robots: HashMap<String, Option<HashSet<String>>>, // the ones taking jobs, with the task types they are limited to
+    retiring: HashSet<String>, // until their last task is over
+}
+
+// handle to submit jobs to a running session, clones submit to the same session
+#[derive(Clone)]
+pub struct JobSender {
+    executor: Executor,
+    state: Arc<Mutex<SenderState>>,
+    stop: Arc<watch::Sender<Stop>>,
+}
+
+impl JobSender {
+    pub fn submit(&self, job: impl Into<Job>) -> Result<(), Error> {
+        let job = job.into();
+        let mut state = self.state.lock().expect("sender lock poisoned");
+        let Some(capabilities) = state.robots.get(&job.robot) else {
+            return Err(Error::UnknownRobot(job.robot));
+        };
+        self.executor.check_task(&job, capabilities.as_ref())?;
+        if state.graph.contains(job.id) {
+            return Err(Error::DuplicateId(job.id));
+        }
+        let tx = state.tx.clone().ok_or(Error::Closed)?;
+        state.graph.add(&job)?;
+        tx.send(Command::Job(job)).map_err(|_| Error::Closed)
+    }
+
+    // a robot taking jobs from now on, in this session only
+    pub fn add_robot(&self, robot: impl Into<String>) -> Result<(), Error> {
+        self.hire(robot.into(), None)
+    }
+
+    // like `add_robot`, for a robot that only does these task types
+    pub fn add_robot_capable_of<I: IntoIterator<Item = S>, S: Into<String>>(&self, robot: impl Into<String>, tasks: I) -> Result<(), Error> {
+        let tasks: HashSet<String> = tasks.into_iter().map(Into::into).collect();
+        if let Some(task) = tasks.iter().find(|task| !self.executor.inner.tasks.contains_key(*task)) {
+            return Err(Error::UnknownTask(task.clone()));
+        }
+        self.hire(robot.into(), Some(tasks))
+    }
+
+    fn hire(&self, robot: String, capabilities: Option<HashSet<String>>) -> Result<(), Error> {
+        let mut state = self.state.lock().expect("sender lock poisoned");
+        if state.robots.contains_key(&robot) || state.retiring.contains(&robot) {
+            return Err(Error::DuplicateRobot(robot));
+        }
+        let tx = state.tx.as_ref().ok_or(Error::Closed)?;
+        tx.send(Command::Hire(robot.clone())).map_err(|_| Error::Closed)?;
+        state.robots.insert(robot, capabilities);
+        Ok(())
+    }
+
+    // the robot takes no more jobs, resolves once its last task is over with the jobs it handed
+    // back, which can be submitted again to another robot, always none with `Retire::Drain`
+    pub async fn retire_robot(&self, robot: &str, when: Retire) -> Result<Vec<Job>, Error> {
+        let (done, handed_back) = oneshot::channel();
+        {
+            let mut state = self.state.lock().expect("sender lock poisoned");
+            if !state.robots.contains_key(robot) {
+                return Err(Error::UnknownRobot(robot.to_owned()));
+            }
+            let tx = state.tx.as_ref().ok_or(Error::Closed)?;
+            tx.send(Command::Retire { robot: robot.to_owned(), when, done }).map_err(|_| Error::Closed)?;
+            state.robots.remove(robot);
+            state.retiring.insert(robot.to_owned());
+        }
+        // dropped without an answer if the session is drained first
+        let handed_back = handed_back.await;
+        let mut state = self.state.lock().expect("sender lock poisoned");
+        state.retiring.remove(robot);
+        let handed_back = handed_back.map_err(|_| Error::Closed)?;
+        for job in &handed_back {
+            state.graph.forget(job.id);
+        }
+        Ok(handed_back)
+    }
+
+    // no more jobs will be accepted, the session ends once the robots have drained their queues
+    pub fn close(&self) {
+        self.state.lock().expect("sender lock poisoned").tx = None;
+    }
+
+    // closes the session and starts nothing more, the jobs not started yet are skipped and the
+    // session ends once the running tasks are over
+    pub fn drain(&self) {
+        self.close();
+        self.stop.send_if_modified(|stop| std::mem::replace(stop, Stop::Drain.max(*stop)) < Stop::Drain);
+    }
+
+    // like `drain`, but the running tasks are dropped too and stay without an outcome in the
+    // report, see `Report::in_flight`
+    pub fn abort(&self) {
+        self.close();
+        self.stop.send_replace(Stop::Abort);
+    }
+}
+
+pub struct Session {
+    sender: JobSender,
+    done: JoinHandle<Report>,
+}
+
+impl Session {
+    pub fn sender(&self) -> JobSender {
+        self.sender.clone()
+    }
+
+    pub fn submit(&self, job: impl Into<Job>) -> Result<(), Error> {
+        self.sender.submit(job)
+    }
+
+    pub fn close(&self) {
+        self.sender.close()
+    }
+
+    pub fn add_robot(&self, robot: impl Into<String>) -> Result<(), Error> {
+        self.sender.add_robot(robot)
+    }
+
+    pub fn add_robot_capable_of<I: IntoIterator<Item = S>, S: Into<String>>(&self, robot: impl Into<String>, tasks: I) -> Result<(), Error> {
+        self.sender.add_robot_capable_of(robot, tasks)
+    }
+
+    pub async fn retire_robot(&self, robot: &str, when: Retire) -> Result<Vec<Job>, Error> {
+        self.sender.retire_robot(robot, when).await
+    }
+
+    pub fn drain(&self) {
+        self.sender.drain()
+    }
+
+    pub fn abort(&self) {
+        self.sender.abort()
+    }
+
+    // waits for the session to end, which only happens once it is closed
+    pub async fn join(self) -> Report {
+        self.done.await.expect("executor panicked")
+    }
+}
+
+// a robot of an idiomatic session, its worker runs until the sender is dropped or it is dismissed
+struct Worker {
+    tx: UnboundedSender<Job>,
+    dismiss: watch::Sender<bool>,
+}
+
+fn hire(tracker: &Arc<Tracker>, robot_name: &str, stop: &watch::Receiver<Stop>, workers: &mut JoinSet<(String, Vec<Job>)>) -> Worker {
+    let (tx, rx) = unbounded_channel::<Job>();
+    let (dismiss, dismissed) = watch::channel(false);
+    workers.spawn(run_robot(tracker.clone(), robot_name.to_owned(), rx, stop.clone(), dismissed));
+    Worker { tx, dismiss }
+}
+
+async fn run_idiomatic(tracker: Arc<Tracker>, mut commands: UnboundedReceiver<Command>, stop: watch::Receiver<Stop>) -> Report {
+    let inner = &tracker.inner;
+
+    let run = async {
+        // dropping the set when aborting drops the workers with it
+        let mut workers = JoinSet::new();
+        let mut robots_senders = HashMap::new();
+        for robot_name in &inner.robots { // prepare execution context
+            robots_senders.insert(robot_name.clone(), hire(&tracker, robot_name, &stop, &mut workers));
+        }
+        let mut retiring: HashMap<String, oneshot::Sender<Vec<Job>>> = HashMap::new();
+
+        // dispatch the tasks to the robots as they come, in order, so each robot keeps its own order
+        loop {
+            let command = tokio::select! {
+                biased;
+                _ = stopping(stop.clone(), Stop::Drain) => None,
+                Some(stopped) = workers.join_next() => {
+                    retired(&mut retiring, stopped);
+                    continue;
+                }
+                command = commands.recv() => command,
+            };
+            match command {
+                Some(Command::Job(job)) => {
+                    tracker.phase(&job, Phase::Queued);
+                    robots_senders[&job.robot].tx.send(job).expect("failed to send task");
+                }
+                Some(Command::Hire(robot)) => {
+                    let worker = hire(&tracker, &robot, &stop, &mut workers);
+                    robots_senders.insert(robot, worker);
+                }
+                Some(Command::Retire { robot, when, done }) => {
+                    // without its sender the worker ends once its queue is empty
+                    let worker = robots_senders.remove(&robot).expect("retired robot is working");
+                    if when == Retire::Now {
+                        worker.dismiss.send_replace(true);
+                    }
+                    retiring.insert(robot, done);
+                }
+                None => {
+                    tracker.seal();
+                    break;
+                }
+            }
+        }
+        // only left when draining, the jobs submitted before that never reached a robot
+        commands.close();
+        while let Ok(command) = commands.try_recv() {
+            if let Command::Job(job) = command {
+                tracker.phase(&job, Phase::Queued);
+                tracker.skip(&job, DRAINED.into());
+            }
+        }
+
+        drop(robots_senders); // the session is closed, droping the senders so the workers can end
+        while let Some(stopped) = workers.join_next().await {
+            retired(&mut retiring, stopped);
+        }
+    };
+    tokio::select! {
+        _ = run => {}
+        _ = stopping(stop.clone(), Stop::Abort) => {}
+    }
+    inner.emit(ExecutorEvent::Done { at: tracker.elapsed() });
+    tracker.report()
+}
+
+// answers the retirement of a robot whose worker is over
+fn retired(retiring: &mut HashMap<String, oneshot::Sender<Vec<Job>>>, stopped: Result<(String, Vec<Job>), JoinError>) {
+    let (robot, handed_back) = stopped.expect("robot panicked");
+    if let Some(done) = retiring.remove(&robot) {
+        let _ = done.send(handed_back); // fine if the caller stopped waiting
+    }
+}
+
+// returns the jobs it hands back when dismissed, the ones it hadn't started
+async fn run_robot(
+    tracker: Arc<Tracker>,
+    robot_name: String,
+    mut rx: UnboundedReceiver<Job>,
+    stop: watch::Receiver<Stop>,
+    dismissed: watch::Receiver<bool>,
+) -> (String, Vec<Job>) {
+    let inner = &tracker.inner;
+    let mut fallout = inner.fallout(&robot_name);
+    let mut handed_back = Vec::new();
+    loop {
+        let job = tokio::select! {
+            biased;
+            _ = dismissal(dismissed.clone()) => None,
+            job = rx.recv() => job,
+        };
+        let Some(job) = job else { break };
+        // the jobs of other robots it depends on may not have run yet
+        tokio::select! {
+            biased;
+            _ = stopping(stop.clone(), Stop::Drain) => {
+                tracker.skip(&job, DRAINED.into());
+                continue;
+            }
+            _ = dismissal(dismissed.clone()) => {
+                handed_back.push(job);
+                break;
+            }
+            _ = tracker.dependencies(&job) => {}
+        }
+        if let Err(reason) = fallout.admit(&job, |id| tracker.finished(id)) {
+            tracker.skip(&job, reason);
+            continue;
+        }
+        // a retry is run by this same loop, so the next jobs of the robot wait for it
+        for attempt in 1.. {
+            // waiting until both the ratelimiter and the concurency limit accross robots let it in,
+            // unless the session is drained or the robot dismissed in the meantime
+            let permit = tokio::select! {
+                biased;
+                _ = stopping(stop.clone(), Stop::Drain) => {
+                    tracker.skip(&job, DRAINED.into());
+                    break;
+                }
+                _ = dismissal(dismissed.clone()) => {
+                    handed_back.push(job.clone());
+                    break;
+                }
+                permit = inner.admission.acquire(&job, |phase| tracker.phase(&job, phase)) => permit,
+            };
+            tracker.phase(&job, Phase::Started);
+            let ran = inner.tasks[&job.task].run(&inner.clock, &job).await;
+            let next = tracker.settle(&job, attempt, ran, &mut fallout);
+            drop(permit);
+            match next {
+                Next::Retry(delay) => tokio::select! {
+                    _ = inner.clock.sleep(delay) => {}
+                    _ = stopping(stop.clone(), Stop::Drain) => {} // skipped by the next attempt
+                    _ = dismissal(dismissed.clone()) => {} // handed back by the next attempt
+                },
+                Next::Done(skip_queued) => {
+                    if let Some(reason) = skip_queued {
+                        while let Ok(next) = rx.try_recv() {
+                            tracker.skip(&next, reason.clone());
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    if *dismissed.borrow() {
+        rx.close();
+        while let Ok(job) = rx.try_recv() {
+            handed_back.push(job);
+        }
+    }
+    for job in &handed_back {
+        tracker.forget(job);
+    }
+    inner.emit(ExecutorEvent::RobotStopped { at: tracker.elapsed(), robot: robot_name.clone() });
+    (robot_name, handed_back)
+}
+
+async fn run_optimized(tracker: Arc<Tracker>, mut commands: UnboundedReceiver<Command>, mut stop: watch::Receiver<Stop>) -> Report {
+    let inner = &tracker.inner;
+    let rates = inner.tasks.iter().map(|(name, task)| (name.clone(), task.rate)).collect();
+    let mut planner = Planner::new(rates, inner.concurrency, inner.aging);
+    let mut running = futures::stream::FuturesUnordered::new();
+    let mut attempts: HashMap<usize, u32> = HashMap::new(); // of the tasks that failed at least once
+    let mut fallouts: HashMap<String, Fallout> = HashMap::new();
+    let mut retiring: Vec<(String, Vec<Job>, oneshot::Sender<Vec<Job>>)> = Vec::new(); // with the jobs handed back
+    let mut open = true;
+    let mut watching = true; // until every sender is gone
+    let mut full = false; // every slot is taken by other runs of this executor
+
+    loop {
+        let stopped = *stop.borrow();
+        match stopped {
+            Stop::Run => {}
+            Stop::Drain => {
+                // what was submitted is still received, and skipped here on the next turn
+                commands.close();
+                for job in planner.drain_all() {
+                    tracker.skip(&job, DRAINED.into());
+                }
+            }
+            Stop::Abort => {
+                inner.emit(ExecutorEvent::Done { at: tracker.elapsed() });
+                return tracker.report(); // dropping the running tasks
+            }
+        }
+
+        // the retired robots are done once their last task is over
+        let (stopped, busy): (Vec<_>, Vec<_>) = std::mem::take(&mut retiring).into_iter().partition(|(robot, ..)| planner.idle(robot));
+        retiring = busy;
+        for (robot, handed_back, done) in stopped {
+            inner.emit(ExecutorEvent::RobotStopped { at: tracker.elapsed(), robot });
+            let _ = done.send(handed_back); // fine if the caller stopped waiting
+        }
+
+        // the jobs whose dependencies have ended are skipped or let through, which may unblock others
+        let skipped = planner.skip(|job| {
+            let fallout = fallouts.entry(job.robot.clone()).or_insert_with(|| inner.fallout(&job.robot));
+            fallout.admit(job, |id| tracker.finished(id)).err()
+        });
+        for (job, reason) in skipped {
+            tracker.skip(&job, reason);
+        }
+
+        // waiting for a slot is done in the select below, so the running tasks of this run still
+        // finish and give theirs back, and a drain or an abort is still noticed
+        let released = inner.admission.released();
+        let mut released = std::pin::pin!(released);
+        released.as_mut().enable();
+        if full && inner.admission.has_slot() {
+            full = false;
+        }
+
+        let step = if full { Step::Wait(None) } else { planner.poll(tracker.elapsed()) };
+        let until = match step {
+            Step::Start { robot, job } => {
+                let task_type = &inner.tasks[&job.task];
+                let permit = match inner.admission.try_acquire(&job.task) {
+                    Ok(permit) => permit,
+                    Err(Some(wait)) => {
+                        // the token was taken by another run of this executor
+                        planner.defer(robot, job, Some(tracker.elapsed() + wait));
+                        continue;
+                    }
+                    Err(None) => {
+                        // so were all the slots, it starts again once one is released
+                        tracker.phase(&job, Phase::WaitingSlot);
+                        planner.defer(robot, job, None);
+                        full = true;
+                        continue;
+                    }
+                };
+                tracker.phase(&job, Phase::Started);
+                running.push(async move {
+                    let ran = task_type.run(&inner.clock, &job).await;
+                    drop(permit);
+                    (robot, job, ran)
+                });
+                continue;
+            }
+            Step::Done if !open => {
+                inner.emit(ExecutorEvent::Done { at: tracker.elapsed() });
+                return tracker.report();
+            }
+            Step::Done => None,
+            Step::Wait(until) => until,
+        };
+        if !full {
+            for (job, phase) in planner.blocked(tracker.elapsed()) {
+                tracker.phase(job, phase);
+            }
+        }
+
+        let sleep = async {
+            match until {
+                Some(offset) => inner.clock.sleep_until(tracker.start + offset).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            command = commands.recv(), if open => match command {
+                Some(command) => {
+                    // everything submitted so far is planned together, rather than the first job
+                    // taking a token that a more urgent one right behind it needed
+                    let mut next = Some(command);
+                    while let Some(command) = next {
+                        match command {
+                            Command::Job(job) => {
+                                tracker.phase(&job, Phase::Queued);
+                                planner.push(job);
+                            }
+                            Command::Hire(_) => {} // robots are planned for as their jobs come
+                            Command::Retire { robot, when, done } => {
+                                let handed_back = match when {
+                                    Retire::Drain => Vec::new(),
+                                    Retire::Now => planner.take(&robot),
+                                };
+                                for job in &handed_back {
+                                    attempts.remove(&job.id);
+                                    tracker.forget(job);
+                                }
+                                retiring.push((robot, handed_back, done));
+                            }
+                        }
+                        next = commands.try_recv().ok();
+                    }
+                }
+                None => {
+                    open = false;
+                    planner.seal();
+                }
+            },
+            Some((robot, job, ran)) = running.next() => {
+                let attempt = attempts.remove(&job.id).map_or(1, |n| n + 1);
+                let fallout = fallouts.entry(job.robot.clone()).or_insert_with(|| inner.fallout(&job.robot));
+                match tracker.settle(&job, attempt, ran, fallout) {
+                    Next::Retry(delay) => {
+                        // back at the front of the robot's queue, the robot idles until then
+                        attempts.insert(job.id, attempt);
+                        planner.retry(robot, job, tracker.elapsed() + delay);
+                    }
+                    Next::Done(skip_queued) => {
+                        planner.finish(robot);
+                        if let Some(reason) = skip_queued {
+                            for next in planner.drain(robot) {
+                                tracker.skip(&next, reason.clone());
+                            }
+                        }
+                    }
+                }
+            }
+            changed = stop.changed(), if watching => watching = changed.is_ok(),
+            _ = &mut released, if full => full = false,
+            _ = sleep => {}
+        }
+    }
+}
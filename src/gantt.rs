@@ -0,0 +1,207 @@
+// gantt charts of a run or of a planned schedule, one lane per robot with its tasks colored by type
+// and the time it spent waiting shaded behind them, as a standalone svg or html page
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+use std::time::Duration;
+use crate::event::Phase;
+use crate::report::{Outcome, Report};
+use crate::schedule::{Schedule, TaskSpec};
+
+#[derive(Debug, Clone)]
+pub struct Bar {
+    pub id: usize,
+    pub task: String,
+    pub start: Duration,
+    pub end: Duration,
+    pub failed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Lane {
+    pub robot: String,
+    pub bars: Vec<Bar>,
+    pub waits: Vec<(Phase, Duration, Duration)>, // what was waited on, from, to
+}
+
+#[derive(Debug, Clone)]
+pub struct Chart {
+    pub title: String,
+    pub lanes: Vec<Lane>, // in the order of the lowest id of each robot
+}
+
+// lanes in the order robots first appear when going through the ids
+fn lanes<'a>(ids: impl Iterator<Item = (usize, &'a str)>) -> (Vec<Lane>, HashMap<&'a str, usize>) {
+    let mut ids: Vec<(usize, &str)> = ids.collect();
+    ids.sort();
+    let mut lanes = Vec::new();
+    let mut index = HashMap::new();
+    for (_, robot) in ids {
+        index.entry(robot).or_insert_with(|| {
+            lanes.push(Lane { robot: robot.to_owned(), bars: Vec::new(), waits: Vec::new() });
+            lanes.len() - 1
+        });
+    }
+    (lanes, index)
+}
+
+impl Chart {
+    // tasks still running when the report was made are drawn up to the last end in the report
+    pub fn from_report(title: impl Into<String>, report: &Report) -> Self {
+        let (mut lanes, index) = lanes(report.tasks.iter().map(|(&id, t)| (id, t.robot.as_str())));
+        let last = report.tasks.values().filter_map(|t| t.end.or(t.start)).max().unwrap_or_default();
+        for (&id, t) in &report.tasks {
+            let lane = &mut lanes[index[t.robot.as_str()]];
+            lane.waits.extend(t.waits.iter().map(|w| (w.phase, w.start, w.end)));
+            for attempt in &t.retries {
+                lane.bars.push(Bar { id, task: t.task.clone(), start: attempt.start, end: attempt.end, failed: true });
+            }
+            if let Some(start) = t.start {
+                let failed = matches!(t.outcome, Outcome::Failed { .. } | Outcome::TimedOut { .. });
+                lane.bars.push(Bar { id, task: t.task.clone(), start, end: t.end.unwrap_or(last), failed });
+            }
+        }
+        Self { title: title.into(), lanes }
+    }
+
+    // a schedule doesn't record waits, they are worked out from the specs, a robot waits on the
+    // ratelimiter from the end of its previous task until the task type is free again, and on a
+    // slot after that
+    pub fn from_schedule(title: impl Into<String>, schedule: &Schedule, specs: &HashMap<String, TaskSpec>) -> Self {
+        let (mut lanes, index) = lanes(schedule.entries.iter().map(|e| (e.id, e.robot.as_str())));
+        let mut ready: HashMap<&str, Duration> = HashMap::new();
+        let mut tats: HashMap<&str, Duration> = HashMap::new(); // theoretical arrival time per task type
+        for e in &schedule.entries {
+            let lane = &mut lanes[index[e.robot.as_str()]];
+            let ready = ready.insert(&e.robot, e.end).unwrap_or_default();
+            let rate = specs[&e.task].rate();
+            let tat = tats.get(e.task.as_str()).copied().unwrap_or_default();
+            tats.insert(&e.task, rate.started(tat, e.start));
+            let token = rate.available_at(tat).max(ready).min(e.start);
+            if token > ready {
+                lane.waits.push((Phase::WaitingRateLimit, ready, token));
+            }
+            if e.start > token {
+                lane.waits.push((Phase::WaitingSlot, token, e.start));
+            }
+            lane.bars.push(Bar { id: e.id, task: e.task.clone(), start: e.start, end: e.end, failed: false });
+        }
+        Self { title: title.into(), lanes }
+    }
+
+    pub fn end(&self) -> Duration {
+        self.lanes.iter()
+            .flat_map(|l| l.bars.iter().map(|b| b.end).chain(l.waits.iter().map(|w| w.2)))
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+const PALETTE: [&str; 8] = ["#4e79a7", "#f28e2b", "#59a14f", "#b07aa1", "#edc948", "#76b7b2", "#ff9da7", "#9c755f"];
+const RATE_LIMIT_SHADE: &str = "#e15759";
+const SLOT_SHADE: &str = "#8c8c8c";
+
+const LABELS: f64 = 70.0; // width of the robot names column
+const PLOT: f64 = 1000.0;
+const LANE: f64 = 28.0;
+const TITLE: f64 = 28.0;
+const AXIS: f64 = 24.0;
+const LEGEND: f64 = 28.0;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// seconds between two ticks, so there are at most 15 of them
+fn tick(end: f64) -> f64 {
+    [1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0]
+        .into_iter()
+        .find(|&step| end / step <= 15.0)
+        .unwrap_or((end / 15.0).ceil())
+}
+
+fn chart_height(chart: &Chart) -> f64 {
+    TITLE + chart.lanes.len() as f64 * LANE + AXIS + LEGEND
+}
+
+// one chart drawn at `top`, every chart of the same image shares the same time scale
+fn draw(out: &mut String, chart: &Chart, top: f64, scale: f64, end: f64, colors: &HashMap<&str, &str>) {
+    let x = |at: Duration| LABELS + at.as_secs_f64() * scale;
+    let _ = writeln!(out, r#"<g transform="translate(0 {top})">"#);
+    let _ = writeln!(out, r#"<text x="0" y="18" font-weight="bold">{}</text>"#, escape(&chart.title));
+    let lanes_bottom = TITLE + chart.lanes.len() as f64 * LANE;
+
+    let mut at = 0.0;
+    let step = tick(end);
+    while at <= end + 1e-9 {
+        let tx = LABELS + at * scale;
+        let _ = writeln!(out, r##"<line x1="{tx:.1}" y1="{TITLE}" x2="{tx:.1}" y2="{lanes_bottom}" stroke="#e0e0e0"/>"##);
+        let _ = writeln!(out, r#"<text x="{tx:.1}" y="{:.1}" font-size="11" text-anchor="middle">{at}s</text>"#, lanes_bottom + 16.0);
+        at += step;
+    }
+
+    for (i, lane) in chart.lanes.iter().enumerate() {
+        let y = TITLE + i as f64 * LANE;
+        let _ = writeln!(out, r#"<text x="0" y="{:.1}" font-size="13">{}</text>"#, y + LANE / 2.0 + 4.0, escape(&lane.robot));
+        for &(phase, start, end) in &lane.waits {
+            let (color, what) = match phase {
+                Phase::WaitingRateLimit => (RATE_LIMIT_SHADE, "ratelimit"),
+                _ => (SLOT_SHADE, "concurrency slot"),
+            };
+            let _ = writeln!(out,
+                r#"<rect x="{:.2}" y="{:.1}" width="{:.2}" height="{:.1}" fill="{color}" fill-opacity="0.25"><title>{} waiting on the {what} {:.3}s -> {:.3}s</title></rect>"#,
+                x(start), y + 2.0, x(end) - x(start), LANE - 4.0, escape(&lane.robot), start.as_secs_f64(), end.as_secs_f64());
+        }
+        for bar in &lane.bars {
+            let stroke = if bar.failed { r##" stroke="#d62728" stroke-width="2" stroke-dasharray="3 2""## } else { "" };
+            let _ = writeln!(out,
+                r#"<rect x="{:.2}" y="{:.1}" width="{:.2}" height="{:.1}" fill="{}"{stroke}><title>id {} {} {:.3}s -> {:.3}s{}</title></rect>"#,
+                x(bar.start), y + 5.0, (x(bar.end) - x(bar.start)).max(1.0), LANE - 10.0, colors[bar.task.as_str()],
+                bar.id, escape(&bar.task), bar.start.as_secs_f64(), bar.end.as_secs_f64(), if bar.failed { " failed" } else { "" });
+        }
+    }
+
+    // legend, task types then the two kinds of wait
+    let y = lanes_bottom + AXIS + 6.0;
+    let mut lx = LABELS;
+    let mut entries: Vec<(&str, &str, f64)> = colors.iter().map(|(&task, &color)| (task, color, 1.0)).collect();
+    entries.sort_by_key(|&(task, ..)| task);
+    entries.push(("waiting on a ratelimiter", RATE_LIMIT_SHADE, 0.25));
+    entries.push(("waiting on a concurrency slot", SLOT_SHADE, 0.25));
+    for (label, color, opacity) in entries {
+        let _ = writeln!(out, r#"<rect x="{lx:.1}" y="{y:.1}" width="12" height="12" fill="{color}" fill-opacity="{opacity}"/>"#);
+        let _ = writeln!(out, r#"<text x="{:.1}" y="{:.1}" font-size="12">{}</text>"#, lx + 16.0, y + 10.0, escape(label));
+        lx += 16.0 + 7.0 * label.len() as f64 + 18.0;
+    }
+    let _ = writeln!(out, "</g>");
+}
+
+// the charts stacked on top of each other, on the same time scale so they can be compared
+pub fn svg(charts: &[Chart]) -> String {
+    let end = charts.iter().map(Chart::end).max().unwrap_or_default().as_secs_f64().max(1.0);
+    let scale = PLOT / end;
+    let tasks: BTreeSet<&str> = charts.iter()
+        .flat_map(|c| c.lanes.iter().flat_map(|l| l.bars.iter().map(|b| b.task.as_str())))
+        .collect();
+    let colors: HashMap<&str, &str> = tasks.into_iter().zip(PALETTE.into_iter().cycle()).collect();
+
+    let width = LABELS + PLOT + 20.0;
+    let height: f64 = charts.iter().map(chart_height).sum();
+    let mut out = String::new();
+    let _ = writeln!(out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif">"#);
+    let mut top = 0.0;
+    for chart in charts {
+        draw(&mut out, chart, top, scale, end, &colors);
+        top += chart_height(chart);
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+pub fn html(charts: &[Chart]) -> String {
+    let title = charts.iter().map(|c| escape(&c.title)).collect::<Vec<_>>().join(", ");
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>body {{ font-family: sans-serif; margin: 2em; }}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        svg(charts))
+}
@@ -0,0 +1,74 @@
+// the order jobs have to run in, each robot's own order plus the `after` of every job, which must
+// never loop back on itself or the robots involved would wait on each other forever
+use std::collections::{HashMap, HashSet};
+use crate::{Error, Job};
+
+// every id is used once, and every job depends on jobs of the list without looping back on itself
+pub(crate) fn check(jobs: &[Job]) -> Result<(), Error> {
+    let mut graph = Graph::default();
+    for job in jobs {
+        if graph.contains(job.id) {
+            return Err(Error::DuplicateId(job.id));
+        }
+        graph.add(job)?;
+    }
+    match graph.missing() {
+        Some((id, after)) => Err(Error::Dependency { id, message: format!("depends on id {after} which isn't in the jobs") }),
+        None => Ok(()),
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Graph {
+    before: HashMap<usize, Vec<usize>>, // what has to end before each job, may name jobs not added yet
+    last: HashMap<String, usize>, // the latest job of each robot
+    ahead: HashSet<usize>, // ids named before they were added, the only ones a loop can close on
+}
+
+impl Graph {
+    pub(crate) fn contains(&self, id: usize) -> bool {
+        self.before.contains_key(&id)
+    }
+
+    // the job may depend on jobs added later, unless one of them ends up waiting on it, which can
+    // only be the case if a job added before named this one
+    pub(crate) fn add(&mut self, job: &Job) -> Result<(), Error> {
+        if job.after.contains(&job.id) {
+            return Err(Error::Dependency { id: job.id, message: String::from("depends on itself") });
+        }
+        let before: Vec<usize> = self.last.get(&job.robot).copied().into_iter().chain(job.after.iter().copied()).collect();
+        let named = if self.ahead.contains(&job.id) { before.as_slice() } else { &[] };
+        for &first in named {
+            let mut stack = vec![first];
+            let mut seen = HashSet::new();
+            while let Some(id) = stack.pop() {
+                if id == job.id {
+                    let message = format!("can't run after id {first}, which already waits for it");
+                    return Err(Error::Dependency { id: job.id, message });
+                }
+                if seen.insert(id) {
+                    stack.extend(self.before.get(&id).into_iter().flatten());
+                }
+            }
+        }
+        self.ahead.remove(&job.id);
+        self.ahead.extend(job.after.iter().copied().filter(|after| !self.before.contains_key(after)));
+        self.before.insert(job.id, before);
+        self.last.insert(job.robot.clone(), job.id);
+        Ok(())
+    }
+
+    // the job was handed back without running, it can be added again, to another robot
+    pub(crate) fn forget(&mut self, id: usize) {
+        self.before.remove(&id);
+        self.last.retain(|_, last| *last != id);
+        if self.before.values().flatten().any(|&before| before == id) {
+            self.ahead.insert(id);
+        }
+    }
+
+    // a job and a dependency of it that was never added
+    pub(crate) fn missing(&self) -> Option<(usize, usize)> {
+        self.before.iter().find_map(|(&id, before)| Some((id, *before.iter().find(|b| !self.contains(**b))?)))
+    }
+}
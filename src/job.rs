@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+// how long a job waits for each level its priority is raised by, see `urgency`
+pub const DEFAULT_AGING: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    pub id: usize,
+    pub robot: String,
+    pub task: String,
+    #[serde(default)]
+    pub after: Vec<usize>, // ids of jobs of any robot that must end before this one starts
+    #[serde(default)]
+    pub priority: i32, // higher goes first when robots compete for a token or a slot
+    #[serde(default, with = "crate::event::secs::option")]
+    pub deadline: Option<Duration>, // on the report's timeline, the earliest goes first between jobs of the same priority
+}
+
+impl Job {
+    pub fn new(id: usize, robot: impl Into<String>, task: impl Into<String>) -> Self {
+        Self { id, robot: robot.into(), task: task.into(), after: Vec::new(), priority: 0, deadline: None }
+    }
+
+    pub fn after(self, after: impl IntoIterator<Item = usize>) -> Self {
+        Self { after: after.into_iter().collect(), ..self }
+    }
+
+    pub fn priority(self, priority: i32) -> Self {
+        Self { priority, ..self }
+    }
+
+    pub fn deadline(self, deadline: Duration) -> Self {
+        Self { deadline: Some(deadline), ..self }
+    }
+}
+
+impl<R: Into<String>, T: Into<String>> From<(usize, R, T)> for Job {
+    fn from((id, robot, task): (usize, R, T)) -> Self {
+        Self::new(id, robot, task)
+    }
+}
+
+// sorts the jobs without a deadline last
+pub(crate) fn due(deadline: Option<Duration>) -> Duration {
+    deadline.unwrap_or(Duration::MAX)
+}
+
+// the priority a job competes with after waiting `waited` for a token or a slot, one level more
+// per `aging` so low priority robots don't starve, only jobs below `top`, the highest priority
+// they compete against, are raised so waiting alone doesn't reorder jobs of the same priority
+pub(crate) fn urgency(priority: i32, waited: Duration, aging: Duration, top: i32) -> i32 {
+    if priority >= top {
+        return priority;
+    }
+    let levels = (waited.as_nanos() / aging.as_nanos()).min(i32::MAX as u128) as i64;
+    (priority as i64 + levels).min(i32::MAX as i64) as i32
+}
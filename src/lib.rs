@@ -0,0 +1,51 @@
+// task executor for a household of robots, see `assignment.py` for the original problem
+//
+// there are two ways to solve this problem
+// one is more idiomatic to rust, but not as deterministic and thus the ordering of task will not be optimal
+// the second will be more deterministic and will try to optimize the ordering of tasks to avoid
+// idle time due to waiting for ratelimiters
+//
+// it is possible to compute the IDEAL ordering of tasks, but it's a NP hard problem you'd
+// typically never implement a solution for in the real world, especially when a non ideal but
+// optimized implementation could get pretty close, for small task lists `exact::solve` does it
+// anyway with a bounded search, which is handy to see how far the other two are from optimal
+mod admission;
+pub mod clock;
+pub mod config;
+mod error;
+pub mod event;
+mod executor;
+mod graph;
+mod job;
+mod optimized;
+mod policy;
+pub mod report;
+mod retry;
+mod task;
+pub mod exact;
+pub mod gantt;
+pub mod load;
+pub mod schedule;
+pub mod simulate;
+pub mod trace;
+pub mod verify;
+
+pub use clock::{Clock, ManualClock, SystemClock, TokioClock};
+pub use error::Error;
+pub use event::{ExecutorEvent, Phase, Subscriber};
+pub use executor::{Executor, ExecutorBuilder, JobSender, Retire, Session};
+pub use job::{DEFAULT_AGING, Job};
+pub use policy::{OnFailure, OnTimeout};
+pub use report::{Attempt, Outcome, Report, TaskResult, Wait};
+pub use retry::RetryPolicy;
+pub use task::{Output, Rate, Registry, Simulated, TaskError, TaskHandler};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strategy {
+    // idiomatic use automatic scheduling, ie, i don't manually manage the ordering of tasks
+    #[default]
+    Idiomatic,
+    // manual scheduling, ordering of tasks is manual and optimized to minimize waiting time due
+    // to ratelimits
+    Optimized,
+}
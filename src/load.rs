@@ -0,0 +1,82 @@
+// reading the job queue from a file, either json lines
+// `{"id":1,"robot":"Dave","task":"clean_the_windows"}` or csv `1,Dave,clean_the_windows`
+// with an optional `id,robot,task` header, the ids a job depends on go in `"after":[1,2]` or in a
+// fourth csv column separated by spaces `3,Dave,feed_the_cat,1 2`, the priority and the deadline in
+// seconds in a fifth and sixth one `4,Cris,feed_the_cat,,5,12.0`, any of the last three can be empty
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::{Error, Job};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    JsonLines,
+    Csv,
+}
+
+impl Format {
+    // json lines if the first non empty line looks like an object, csv otherwise
+    pub fn detect(input: &str) -> Self {
+        match input.lines().map(str::trim).find(|l| !l.is_empty()) {
+            Some(line) if line.starts_with('{') => Format::JsonLines,
+            _ => Format::Csv,
+        }
+    }
+}
+
+// a single job, `None` for a csv header
+pub fn parse_line(line: &str, format: Format) -> Result<Option<Job>, String> {
+    match format {
+        Format::JsonLines => serde_json::from_str(line).map(Some).map_err(|e| e.to_string()),
+        Format::Csv => {
+            const HEADER: [&str; 6] = ["id", "robot", "task", "after", "priority", "deadline"];
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields.len() >= 3 && HEADER.starts_with(&fields) {
+                return Ok(None);
+            }
+            if !(3..=HEADER.len()).contains(&fields.len()) {
+                return Err(format!("expected 3 to 6 fields ({}), got {}", HEADER.join(","), fields.len()));
+            }
+            let field = |i: usize| fields.get(i).copied().unwrap_or_default();
+            let id = field(0).parse().map_err(|_| format!("invalid id {:?}", field(0)))?;
+            let after = field(3).split_whitespace()
+                .map(|after| after.parse().map_err(|_| format!("invalid id {after:?} in after")))
+                .collect::<Result<Vec<usize>, String>>()?;
+            let mut job = Job::new(id, field(1), field(2)).after(after);
+            if !field(4).is_empty() {
+                job = job.priority(field(4).parse().map_err(|_| format!("invalid priority {:?}", field(4)))?);
+            }
+            if !field(5).is_empty() {
+                let deadline = field(5).parse().ok().and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+                job = job.deadline(deadline.ok_or_else(|| format!("invalid deadline {:?}, expected seconds", field(5)))?);
+            }
+            Ok(Some(job))
+        }
+    }
+}
+
+// blank lines are skipped, line numbers in errors start at 1
+pub fn parse(input: &str, format: Format) -> Result<Vec<Job>, Error> {
+    let mut jobs = Vec::new();
+    let mut seen = HashMap::new();
+    for (i, line) in input.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let line_number = i + 1;
+        let parsed = parse_line(line.trim(), format).map_err(|message| Error::Parse { line: line_number, message })?;
+        let Some(job) = parsed else { continue };
+        if let Some(first) = seen.insert(job.id, line_number) {
+            return Err(Error::Parse { line: line_number, message: format!("duplicate id {} (first used on line {first})", job.id) });
+        }
+        jobs.push(job);
+    }
+    Ok(jobs)
+}
+
+// `-` reads from stdin
+pub fn read(path: &str) -> Result<Vec<Job>, Error> {
+    let input = if path == "-" {
+        std::io::read_to_string(std::io::stdin())
+    } else {
+        std::fs::read_to_string(path)
+    };
+    let input = input.map_err(|e| Error::Io(format!("{path} : {e}")))?;
+    parse(&input, Format::detect(&input))
+}
@@ -1,141 +1,290 @@
-#![allow(unused)]
-fn ratelimiter_with_interval(interval_sec: u64) -> governor::DefaultDirectRateLimiter {
-    governor::RateLimiter::direct(
-        governor::Quota::with_period(
-            std::time::Duration::from_secs(interval_sec)).expect("failed to setup ratelimiter")
-    )
-}
+use std::collections::HashMap;
+use std::time::Duration;
+use robot_tech_test::schedule::{Schedule, TaskSpec};
+use robot_tech_test::config::Config;
+use robot_tech_test::event::JsonLinesPrinter;
+use robot_tech_test::gantt::{self, Chart};
+use robot_tech_test::verify::{self, Timeline};
+use robot_tech_test::{DEFAULT_AGING, Executor, Job, Report, Session, Strategy, TaskError, clock, exact, load, simulate, trace};
 
-async fn clean_the_windows(_task_id: usize, _robot_name: &str) -> String {
+async fn clean_the_windows(_task_id: usize, _robot_name: String) -> Result<String, TaskError> {
     // Simulated execution time (0.3 seconds)
-    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
-    String::from("Squeeesh")
+    clock::sleep(Duration::from_millis(300)).await;
+    Ok(String::from("Squeeesh"))
 }
 
-async fn water_the_plants(_task_id: usize, _robot_name: &str) -> String {
+async fn water_the_plants(_task_id: usize, _robot_name: String) -> Result<String, TaskError> {
     // Simulated execution time (0.7 seconds)
-    tokio::time::sleep(std::time::Duration::from_millis(700)).await;
-    String::from("Blub")
+    clock::sleep(Duration::from_millis(700)).await;
+    Ok(String::from("Blub"))
 }
 
-async fn feed_the_cat(_task_id: usize, _robot_name: &str) -> String {
+async fn feed_the_cat(_task_id: usize, _robot_name: String) -> Result<String, TaskError> {
     // Simulated execution time (0.5 seconds)
-    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-    String::from("Meow")
+    clock::sleep(Duration::from_millis(500)).await;
+    Ok(String::from("Meow"))
 }
 
-async fn execute_task(task_name: &str, task_id: usize, robot_name: &str) -> String {
-    match task_name {
-        "clean_the_windows" => clean_the_windows(task_id, robot_name).await,
-        "water_the_plants" => water_the_plants(task_id, robot_name).await,
-        "feed_the_cat" => feed_the_cat(task_id, robot_name).await,
-        _ => panic!("invalid task_name")
-    }
+// durations of the tasks above and their ratelimits, used when planning a run ahead of time
+fn task_specs() -> HashMap<String, TaskSpec> {
+    HashMap::from([
+        ("clean_the_windows".into(), TaskSpec { duration: Duration::from_millis(300), interval: Duration::from_secs(5), burst: 1 }),
+        ("water_the_plants".into(), TaskSpec { duration: Duration::from_millis(700), interval: Duration::from_secs(3), burst: 1 }),
+        ("feed_the_cat".into(), TaskSpec { duration: Duration::from_millis(500), interval: Duration::from_secs(2), burst: 1 }),
+    ])
 }
 
-// there are two ways to solve this problem
-// one is more idiomatic to rust, but not as deterministic and thus the ordering of task will not be optimal
-// the second will be more deterministic and will try to optimize the ordering of tasks to avoid
-// idle time due to waiting for ratelimiters
-//
-// it is possible to compute the IDEAL ordering of tasks, but it's a NP hard problem you'd
-// typically never implement a solution for in the real world, especially when a non ideal but
-// optimized implementation could get pretty close
-
-// idiomatic use automatic scheduling, ie, i don't manually manage the ordering of tasks
-mod idiomatic {
-    use std::collections::HashMap;
-    use tokio::sync::mpsc::unbounded_channel;
-
-    pub async fn solve(tasks: Vec<(usize, &str, &str)>) {
-        let mut robots_senders = HashMap::new();
-        let mut handles = Vec::new();
-
-        let task_config = std::sync::Arc::new(HashMap::from([
-                ("clean_the_windows", super::ratelimiter_with_interval(5)),
-                ("water_the_plants", super::ratelimiter_with_interval(3)),
-                ("feed_the_cat", super::ratelimiter_with_interval(2)),
-        ]));
-
-        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(3)); // concurency of 3
-
-        for robot_name in ["Dave", "Cris", "Andi", "Nick", "Phil", "Maxi"] { // prepare execution context
-            let (tx, mut rx) = unbounded_channel::<(usize, String)>();
-            robots_senders.insert(robot_name, tx);
-
-            let sem = semaphore.clone();
-            let task_config = task_config.clone();
-            let handle = tokio::task::spawn(async move {
-                while let Some((task_id, task)) = rx.recv().await {
-                    let Some(rt) = task_config.get(task.as_str()) else {
-                        println!("invalid task name : {task}");
-                        continue;
-                    };
-                    println!("{robot_name} waiting for {task} with id {task_id}");
-                    rt.until_ready().await; // waiting on the ratelimiter
-                    let _permit = sem.acquire().await; // to limit concurency accross robots
-                    println!("{robot_name} started {task} with id {task_id}");
-                    super::execute_task(&task, task_id, robot_name).await;
-                    println!("{robot_name} finished {task} with id {task_id}")
-                }
-                println!("robot : {robot_name} finished working")
-            });
-            handles.push(handle);
+// the exit code of a run cut short by a signal, like a shell reports it for ctrl-c
+const INTERRUPTED: i32 = 130;
+
+fn exit_with(message: String) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1)
+}
+
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|a| a == name) {
+        Some(i) => {
+            args.remove(i);
+            true
         }
+        None => false,
+    }
+}
+
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == name)?;
+    if i + 1 >= args.len() {
+        exit_with(format!("{name} needs a value"));
+    }
+    args.drain(i..=i + 1).nth(1)
+}
+
+// the report is printed unless events are printed as json, and written as json if asked to
+fn output_report(report: &Report, json: bool, path: Option<&str>) {
+    if !json {
+        report.print();
+    }
+    if let Some(path) = path {
+        let report = serde_json::to_string_pretty(report).expect("reports always serialize");
+        std::fs::write(path, report).unwrap_or_else(|e| exit_with(format!("failed to write {path} : {e}")));
+    }
+}
+
+// `.html` files get a page, anything else an svg
+fn write_gantt(path: &str, charts: &[Chart]) {
+    let output = if path.ends_with(".html") { gantt::html(charts) } else { gantt::svg(charts) };
+    std::fs::write(path, output).unwrap_or_else(|e| exit_with(format!("failed to write {path} : {e}")));
+}
+
+fn write_trace(path: &str, report: &Report, executor: &Executor) {
+    let trace = trace::trace(report, &executor.limits()).to_string();
+    std::fs::write(path, trace).unwrap_or_else(|e| exit_with(format!("failed to write {path} : {e}")));
+}
 
-        // dispatch the tasks to the robots
+// the deadlines a planned run would miss
+fn print_missed(schedule: &Schedule, jobs: &[Job]) {
+    for (id, deadline, end) in schedule.missed_deadlines(jobs) {
+        let end = end.map_or(String::from("never runs"), |end| format!("ends at {:.3}s", end.as_secs_f64()));
+        println!("id {id} would miss its deadline of {:.3}s, it {end}", deadline.as_secs_f64());
+    }
+}
 
-        for (id, robot, task_name) in tasks {
-            let robot_handle = robots_senders.get(robot).expect(&format!("unknown robot {robot}"));
-            robot_handle.send((id, task_name.into())).expect("failed to send task");
+// ctrl-c, or sigterm on unix
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut terminate = signal(SignalKind::terminate()).unwrap_or_else(|e| exit_with(format!("failed to listen to sigterm : {e}")));
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
         }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
 
-        drop(robots_senders); // droping the senders so the handles can end, this could be optional if we
-                              // wanted to add more tasks as we are going
-        futures::future::try_join_all(handles).await;
-        println!("all tasks have been done")
+// waits for the session to end, the first signal (or `interrupted`) stops starting tasks and lets
+// the running ones finish for up to `grace`, a second signal or the end of the grace period aborts
+// them, true with the report if the session was drained or aborted
+async fn finish(session: Session, grace: Duration, interrupted: bool) -> (Report, bool) {
+    let sender = session.sender();
+    let mut join = std::pin::pin!(session.join());
+    if !interrupted {
+        tokio::select! {
+            report = &mut join => return (report, false),
+            _ = shutdown_signal() => {}
+        }
     }
+    eprintln!("shutting down, waiting up to {:.1}s for the running tasks, signal again to abort them", grace.as_secs_f64());
+    sender.drain();
+    tokio::select! {
+        report = &mut join => return (report, true),
+        _ = shutdown_signal() => eprintln!("aborting the running tasks"),
+        _ = tokio::time::sleep(grace) => eprintln!("grace period over, aborting the running tasks"),
+    }
+    sender.abort();
+    (join.await, true)
 }
 
+// reads jobs from stdin as they come and runs them, until stdin is closed or a signal
+async fn stream(executor: &Executor, grace: Duration) -> (Report, bool) {
+    use tokio::io::AsyncBufReadExt;
 
-// TODO manual scheduling, ordering of tasks is manual and optimized to minimize waiting time due to
-// ratelimits, i can write it if you ask for it
-mod optimized {
+    let session = executor.start();
+    let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+    let mut signal = std::pin::pin!(shutdown_signal());
+    let mut interrupted = false;
+    loop {
+        let line = tokio::select! {
+            line = lines.next_line() => line,
+            _ = &mut signal => {
+                interrupted = true;
+                break;
+            }
+        };
+        let Ok(Some(line)) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match load::parse_line(line.trim(), load::Format::detect(&line)) {
+            Ok(Some(job)) => {
+                if let Err(e) = session.submit(job) {
+                    eprintln!("rejected job : {e}");
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("invalid job : {e}"),
+        }
+    }
+    session.close();
+    finish(session, grace, interrupted).await
 }
 
+// usage : robot_tech_test [idiomatic|optimized|simulate|exact] [--config household.toml] [--json]
+//                         [--report report.json] [--gantt chart.svg|chart.html] [--trace trace.json]
+//                         [--grace 10] [--stream | jobs file, `-` for stdin]
+//         robot_tech_test verify [--config household.toml] <events or report file, `-` for stdin>
 #[tokio::main]
 async fn main() {
-    let tasks = vec![
-        (1, "Dave", "clean_the_windows"),
-        (2, "Dave", "water_the_plants"),
-        (3, "Dave", "clean_the_windows"),
-        (4, "Dave", "feed_the_cat"),
-        (5, "Dave", "clean_the_windows"),
-        (6, "Cris", "water_the_plants"),
-        (7, "Cris", "clean_the_windows"),
-        (8, "Cris", "clean_the_windows"),
-        (9, "Cris", "feed_the_cat"),
-        (10, "Cris", "water_the_plants"),
-        (11, "Andi", "clean_the_windows"),
-        (12, "Andi", "water_the_plants"),
-        (13, "Andi", "clean_the_windows"),
-        (14, "Andi", "feed_the_cat"),
-        (15, "Andi", "clean_the_windows"),
-        (16, "Nick", "water_the_plants"),
-        (17, "Nick", "clean_the_windows"),
-        (18, "Nick", "clean_the_windows"),
-        (19, "Nick", "feed_the_cat"),
-        (20, "Nick", "water_the_plants"),
-        (21, "Phil", "clean_the_windows"),
-        (22, "Phil", "water_the_plants"),
-        (23, "Phil", "clean_the_windows"),
-        (24, "Phil", "feed_the_cat"),
-        (25, "Phil", "clean_the_windows"),
-        (26, "Maxi", "water_the_plants"),
-        (27, "Maxi", "clean_the_windows"),
-        (28, "Maxi", "clean_the_windows"),
-        (29, "Maxi", "feed_the_cat"),
-        (30, "Maxi", "water_the_plants")
-    ];
-    idiomatic::solve(tasks).await;
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let config = take_option(&mut args, "--config")
+        .map(|path| Config::read(&path).unwrap_or_else(|e| exit_with(format!("failed to load {path} : {e}"))));
+    let report_path = take_option(&mut args, "--report");
+    let gantt_path = take_option(&mut args, "--gantt");
+    let trace_path = take_option(&mut args, "--trace");
+    // seconds the running tasks get to finish after ctrl-c or sigterm
+    let grace = take_option(&mut args, "--grace").map_or(Duration::from_secs(10), |grace| {
+        grace.parse().ok().and_then(|grace| Duration::try_from_secs_f64(grace).ok())
+            .unwrap_or_else(|| exit_with(format!("invalid grace period {grace:?}, expected seconds")))
+    });
+    let streaming = take_flag(&mut args, "--stream");
+    let json = take_flag(&mut args, "--json");
+    let mode = match args.first().map(String::as_str) {
+        Some("idiomatic" | "optimized" | "simulate" | "exact" | "verify") => args.remove(0),
+        _ => String::from("idiomatic"),
+    };
+
+    // without a config, the household of the assignment
+    let (builder, specs, concurrency) = match &config {
+        Some(config) => (config.builder(), config.specs(), config.concurrency),
+        None => {
+            let builder = Executor::builder()
+                .robots(["Dave", "Cris", "Andi", "Nick", "Phil", "Maxi"])
+                .task("clean_the_windows", Duration::from_secs(5), clean_the_windows)
+                .task("water_the_plants", Duration::from_secs(3), water_the_plants)
+                .task("feed_the_cat", Duration::from_secs(2), feed_the_cat)
+                .concurrency(3); // concurency of 3
+            (builder, task_specs(), 3)
+        }
+    };
+    let builder = if json { builder.subscriber(JsonLinesPrinter) } else { builder };
+    let executor = builder
+        .strategy(if mode == "optimized" { Strategy::Optimized } else { Strategy::Idiomatic })
+        .build()
+        .unwrap_or_else(|e| exit_with(format!("failed to setup executor : {e}")));
+
+    if mode == "verify" {
+        let path = args.first().unwrap_or_else(|| exit_with(String::from("verify needs the events or the report of a run")));
+        let timeline = Timeline::read(path).unwrap_or_else(|e| exit_with(format!("failed to load {path} : {e}")));
+        let violations = verify::verify(&timeline, &executor.limits());
+        for violation in &violations {
+            println!("{violation}");
+        }
+        if !violations.is_empty() {
+            exit_with(format!("{} violations in {} tasks", violations.len(), timeline.entries.len()));
+        }
+        return println!("no violations in {} tasks", timeline.entries.len());
+    }
+
+    if streaming {
+        if mode == "simulate" || mode == "exact" {
+            exit_with(format!("{mode} needs the whole job list, it can't be used with --stream"));
+        }
+        let (report, interrupted) = stream(&executor, grace).await;
+        if let Some(path) = &gantt_path {
+            write_gantt(path, &[Chart::from_report(&mode, &report)]);
+        }
+        if let Some(path) = &trace_path {
+            write_trace(path, &report, &executor);
+        }
+        output_report(&report, json, report_path.as_deref());
+        if interrupted {
+            std::process::exit(INTERRUPTED);
+        }
+        return;
+    }
+
+    let path = args.first().map(String::as_str).unwrap_or("jobs.jsonl");
+    let tasks = load::read(path).unwrap_or_else(|e| exit_with(format!("failed to load jobs : {e}")));
+    // reject bad jobs before anything runs
+    executor.validate(&tasks).unwrap_or_else(|e| exit_with(format!("invalid jobs : {e}")));
+
+    match mode.as_str() {
+        "simulate" => {
+            let mut charts = Vec::new();
+            for strategy in [Strategy::Idiomatic, Strategy::Optimized] {
+                println!("{strategy:?}");
+                let aging = config.as_ref().and_then(|c| c.aging).map_or(DEFAULT_AGING, Duration::from_secs_f64);
+                let schedule = simulate::simulate(&tasks, &specs, concurrency, aging, strategy)
+                    .unwrap_or_else(|e| exit_with(format!("invalid jobs : {e}")));
+                schedule.print();
+                print_missed(&schedule, &tasks);
+                charts.push(Chart::from_schedule(format!("{strategy:?} (simulated)"), &schedule, &specs));
+            }
+            if let Some(path) = &gantt_path {
+                write_gantt(path, &charts);
+            }
+        }
+        "exact" => {
+            let solution = exact::solve(&tasks, &specs, concurrency, exact::Budget::default())
+                .unwrap_or_else(|e| exit_with(format!("invalid jobs : {e}")));
+            solution.schedule.print();
+            print_missed(&solution.schedule, &tasks);
+            println!("lower bound : {:.3}s, optimal : {}, explored {} nodes",
+                solution.lower_bound.as_secs_f64(), solution.optimal, solution.nodes);
+            if let Some(path) = &gantt_path {
+                write_gantt(path, &[Chart::from_schedule("exact", &solution.schedule, &specs)]);
+            }
+        }
+        _ => {
+            let session = executor.start();
+            for job in tasks {
+                session.submit(job).unwrap_or_else(|e| exit_with(format!("invalid jobs : {e}")));
+            }
+            session.close();
+            let (report, interrupted) = finish(session, grace, false).await;
+            if let Some(path) = &gantt_path {
+                write_gantt(path, &[Chart::from_report(&mode, &report)]);
+            }
+            if let Some(path) = &trace_path {
+                write_trace(path, &report, &executor);
+            }
+            output_report(&report, json, report_path.as_deref());
+            if interrupted {
+                std::process::exit(INTERRUPTED);
+            }
+        }
+    }
 }
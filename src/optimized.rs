@@ -0,0 +1,257 @@
+// manual scheduling, a single dispatcher owns every robot queue and tracks when each task type
+// will be available again, replaying the cell rate algorithm of the ratelimiters so bursts are
+// planned for too, a rate limit "token" is only taken at the exact moment a task starts, so it is
+// never wasted on a task that then waits for a concurrency slot
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use crate::{Job, Rate};
+use crate::event::Phase;
+use crate::job::{due, urgency};
+
+struct Robot {
+    name: String,
+    queue: VecDeque<Job>,
+    busy: bool,
+    backoff: Duration, // a retried task can't start again before this
+    since: Option<Duration>, // when its next job could have started if it were let in, for aging
+    current: Option<usize>, // the running job
+}
+
+pub(crate) enum Step {
+    Start { robot: usize, job: Job },
+    Wait(Option<Duration>), // until the given offset from the start, or until a running task finishes
+    Done, // nothing left to run until more jobs are pushed
+}
+
+// pure scheduling state, all times are offsets from the start of the run so it can be driven
+// by the real clock or by a simulated one
+pub(crate) struct Planner {
+    robots: Vec<Robot>,
+    rates: HashMap<String, Rate>,
+    tats: HashMap<String, Duration>, // theoretical arrival time of each task type's ratelimiter
+    pending: HashMap<String, u32>, // tasks not started yet per task type
+    running: usize,
+    concurrency: usize,
+    aging: Duration,
+    known: HashSet<usize>, // every job pushed so far
+    ended: HashSet<usize>, // the jobs that ran or were taken out
+    sealed: bool, // no more jobs will be pushed
+}
+
+impl Planner {
+    pub(crate) fn new(rates: HashMap<String, Rate>, concurrency: usize, aging: Duration) -> Self {
+        Self {
+            robots: Vec::new(),
+            rates,
+            tats: HashMap::new(),
+            pending: HashMap::new(),
+            running: 0,
+            concurrency,
+            aging,
+            known: HashSet::new(),
+            ended: HashSet::new(),
+            sealed: false,
+        }
+    }
+
+    // no more jobs are coming, a dependency that was never pushed won't hold anything back anymore
+    pub(crate) fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    // every job this one depends on has ended
+    fn ready(&self, job: &Job) -> bool {
+        job.after.iter().all(|id| self.ended.contains(id) || (self.sealed && !self.known.contains(id)))
+    }
+
+    // the robot has nothing running nor queued
+    pub(crate) fn idle(&self, name: &str) -> bool {
+        self.robots.iter().find(|r| r.name == name).is_none_or(|r| !r.busy && r.queue.is_empty())
+    }
+
+    // the jobs of the robot that haven't started, handed back so they can be pushed again
+    pub(crate) fn take(&mut self, name: &str) -> Vec<Job> {
+        let Some(robot) = self.robots.iter_mut().find(|r| r.name == name) else { return Vec::new() };
+        let jobs: Vec<Job> = robot.queue.drain(..).collect();
+        robot.since = None;
+        robot.backoff = Duration::ZERO;
+        for job in &jobs {
+            *self.pending.get_mut(&job.task).expect("task is pending") -= 1;
+            self.known.remove(&job.id);
+        }
+        jobs
+    }
+
+    // takes the next job of each idle robot out while `skip` gives a reason to, once its
+    // dependencies have ended, in case that lets other jobs through
+    pub(crate) fn skip(&mut self, mut skip: impl FnMut(&Job) -> Option<String>) -> Vec<(Job, String)> {
+        let mut skipped = Vec::new();
+        loop {
+            let before = skipped.len();
+            for i in 0..self.robots.len() {
+                while let Some(job) = self.robots[i].queue.front().filter(|job| !self.robots[i].busy && self.ready(job)) {
+                    let Some(reason) = skip(job) else { break };
+                    let job = self.robots[i].queue.pop_front().expect("robot has a task");
+                    *self.pending.get_mut(&job.task).expect("task is pending") -= 1;
+                    self.ended.insert(job.id);
+                    skipped.push((job, reason));
+                }
+            }
+            if skipped.len() == before {
+                return skipped;
+            }
+        }
+    }
+
+    // jobs can be pushed at any time, each robot still runs its jobs in the order they were pushed
+    pub(crate) fn push(&mut self, job: Job) {
+        let robot = match self.robots.iter().position(|r| r.name == job.robot) {
+            Some(i) => i,
+            None => {
+                self.robots.push(Robot {
+                    name: job.robot.clone(), queue: VecDeque::new(), busy: false, backoff: Duration::ZERO, since: None, current: None,
+                });
+                self.robots.len() - 1
+            }
+        };
+        *self.pending.entry(job.task.clone()).or_insert(0) += 1;
+        self.known.insert(job.id);
+        self.robots[robot].queue.push_back(job);
+    }
+
+    // remaining ratelimited time for a task type, the biggest one is the bottleneck of the run
+    fn weight(&self, task: &str) -> Duration {
+        self.rates[task].interval * self.pending[task]
+    }
+
+    // how much ratelimited work is still stuck behind this robot
+    fn load(&self, robot: &Robot) -> Duration {
+        robot.queue.iter().map(|job| self.rates[&job.task].interval).sum()
+    }
+
+    fn tat(&self, task: &str) -> Duration {
+        self.tats.get(task).copied().unwrap_or_default()
+    }
+
+    fn available_at(&self, task: &str) -> Duration {
+        self.rates[task].available_at(self.tat(task))
+    }
+
+    pub(crate) fn poll(&mut self, now: Duration) -> Step {
+        for i in 0..self.robots.len() {
+            let robot = &self.robots[i];
+            if !robot.busy && robot.backoff <= now && robot.queue.front().is_some_and(|job| self.ready(job)) {
+                self.robots[i].since.get_or_insert(now);
+            }
+        }
+
+        if self.running < self.concurrency {
+            // among the robots that could start right now, favor the most urgent job, then the
+            // earliest deadline, then the bottleneck task type, then the robot that has the most
+            // ratelimited work left behind it
+            let ready = |i: &usize| -> Option<(usize, &Job)> {
+                let robot = &self.robots[*i];
+                Some((*i, robot.queue.front()?)).filter(|(_, job)| !robot.busy && robot.backoff <= now && self.ready(job))
+            };
+            let top = (0..self.robots.len()).filter_map(|i| ready(&i)).map(|(_, job)| job.priority).max().unwrap_or_default();
+            let best = (0..self.robots.len())
+                .filter_map(|i| ready(&i))
+                .filter(|&(_, job)| self.available_at(&job.task) <= now)
+                .max_by_key(|&(i, job)| {
+                    let waited = now.saturating_sub(self.robots[i].since.unwrap_or(now));
+                    let urgency = urgency(job.priority, waited, self.aging, top);
+                    (urgency, Reverse(due(job.deadline)), self.weight(&job.task), self.load(&self.robots[i]), Reverse(i))
+                })
+                .map(|(i, _)| i);
+
+            if let Some(i) = best {
+                let robot = &mut self.robots[i];
+                let job = robot.queue.pop_front().expect("robot has a task");
+                robot.busy = true;
+                robot.since = None;
+                robot.current = Some(job.id);
+                self.running += 1;
+                self.tats.insert(job.task.clone(), self.rates[&job.task].started(self.tat(&job.task), now));
+                *self.pending.get_mut(&job.task).expect("task is pending") -= 1;
+                return Step::Start { robot: i, job };
+            }
+        }
+
+        if self.running == 0 && self.robots.iter().all(|r| r.queue.is_empty()) {
+            return Step::Done;
+        }
+
+        // nothing can start now, look ahead at the next ratelimiter or backoff that unblocks an idle
+        // robot, the ones waiting on a dependency are woken up by the task they wait for ending
+        let wake = if self.running < self.concurrency {
+            self.robots.iter()
+                .filter(|r| !r.busy)
+                .filter_map(|r| r.queue.front().filter(|job| self.ready(job)).map(|job| self.available_at(&job.task).max(r.backoff)))
+                .min()
+        } else {
+            None
+        };
+        Step::Wait(wake)
+    }
+
+    // what the idle robots are waiting on to start their next job, only meaningful after `poll`
+    // returned `Wait`
+    pub(crate) fn blocked(&self, now: Duration) -> impl Iterator<Item = (&Job, Phase)> {
+        self.robots.iter()
+            .filter(move |r| !r.busy && r.backoff <= now)
+            .filter_map(|r| r.queue.front().filter(|job| self.ready(job)))
+            .map(move |job| match self.available_at(&job.task) > now {
+                true => (job, Phase::WaitingRateLimit),
+                false => (job, Phase::WaitingSlot),
+            })
+    }
+
+    pub(crate) fn finish(&mut self, robot: usize) {
+        if let Some(id) = self.robots[robot].current {
+            self.ended.insert(id);
+        }
+        self.release(robot);
+    }
+
+    fn release(&mut self, robot: usize) {
+        self.robots[robot].busy = false;
+        self.robots[robot].current = None;
+        self.running -= 1;
+    }
+
+    // the job failed and runs again first thing once `at` is reached, the robot stays idle until then
+    pub(crate) fn retry(&mut self, robot: usize, job: Job, at: Duration) {
+        *self.pending.get_mut(&job.task).expect("task is pending") += 1;
+        self.robots[robot].queue.push_front(job);
+        self.robots[robot].backoff = at;
+        self.release(robot);
+    }
+
+    // takes every job out of the robot's queue
+    pub(crate) fn drain(&mut self, robot: usize) -> Vec<Job> {
+        self.robots[robot].since = None;
+        let jobs: Vec<Job> = self.robots[robot].queue.drain(..).collect();
+        for job in &jobs {
+            *self.pending.get_mut(&job.task).expect("task is pending") -= 1;
+            self.ended.insert(job.id);
+        }
+        jobs
+    }
+
+    // takes every job out of every queue
+    pub(crate) fn drain_all(&mut self) -> Vec<Job> {
+        (0..self.robots.len()).flat_map(|robot| self.drain(robot)).collect()
+    }
+
+    // undo the last start of this robot, for when the slots turn out to be taken by something the
+    // planner doesn't know about, or the ratelimiter is until `until`
+    pub(crate) fn defer(&mut self, robot: usize, job: Job, until: Option<Duration>) {
+        let rate = self.rates[&job.task];
+        let tat = self.tat(&job.task).saturating_sub(rate.interval);
+        self.tats.insert(job.task.clone(), until.map_or(tat, |until| tat.max(until + rate.tolerance())));
+        *self.pending.get_mut(&job.task).expect("task is pending") += 1;
+        self.robots[robot].queue.push_front(job);
+        self.release(robot);
+    }
+}
@@ -0,0 +1,66 @@
+// what a robot does with the rest of its queue when one of its tasks doesn't end well
+use serde::Deserialize;
+use crate::Job;
+use crate::report::Outcome;
+
+// after a task timed out, set per task type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnTimeout {
+    #[default]
+    Continue, // the next jobs run as usual
+    Skip, // the jobs already queued for the robot are skipped, the ones submitted later still run
+    Halt, // the robot runs nothing anymore, every job queued or submitted later is skipped
+}
+
+// after a task failed for good, once its retries are spent, set per robot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnFailure {
+    #[default]
+    Continue, // every job runs, even the ones depending on a task that didn't finish
+    SkipDependents, // the jobs depending on a task that didn't finish are skipped, whatever its robot
+    Halt, // the robot runs nothing anymore, every job queued or submitted later is skipped
+}
+
+// what one robot can't run anymore because of the tasks that didn't end well
+pub(crate) struct Fallout {
+    on_failure: OnFailure,
+    halted: Option<String>, // why the robot stopped
+}
+
+impl Fallout {
+    pub(crate) fn new(on_failure: OnFailure) -> Self {
+        Self { on_failure, halted: None }
+    }
+
+    // records how a task ended, returns why the jobs already queued behind it are all skipped
+    // when they are
+    pub(crate) fn ended(&mut self, job: &Job, outcome: &Outcome, on_timeout: OnTimeout) -> Option<String> {
+        let (what, halt) = match outcome {
+            Outcome::Failed { .. } => ("failed", self.on_failure == OnFailure::Halt),
+            Outcome::TimedOut { .. } => ("timed out", on_timeout == OnTimeout::Halt),
+            _ => return None,
+        };
+        if halt {
+            self.halted = Some(format!("{} halted after id {} {what}", job.robot, job.id));
+            return self.halted.clone();
+        }
+        match (outcome, on_timeout) {
+            (Outcome::TimedOut { .. }, OnTimeout::Skip) => Some(format!("id {} timed out before it", job.id)),
+            _ => None,
+        }
+    }
+
+    // why the job must be skipped instead of run, if it must, once its dependencies have ended,
+    // `finished` tells which of them finished
+    pub(crate) fn admit(&self, job: &Job, finished: impl Fn(usize) -> bool) -> Result<(), String> {
+        if let Some(reason) = &self.halted {
+            return Err(reason.clone());
+        }
+        match job.after.iter().find(|&&id| !finished(id)) {
+            Some(id) if self.on_failure != OnFailure::Continue => Err(format!("depends on id {id} which didn't finish")),
+            _ => Ok(()),
+        }
+    }
+}
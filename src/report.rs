@@ -0,0 +1,142 @@
+// what happened to every task of a session
+use std::collections::BTreeMap;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::event::{Phase, secs};
+use crate::task::Output;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    Pending, // never finished
+    Finished { output: Output },
+    Failed { error: String },
+    TimedOut {
+        #[serde(with = "secs")]
+        after: Duration,
+    },
+    Skipped { reason: String }, // never started, on purpose
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskResult {
+    pub robot: String,
+    pub task: String,
+    pub outcome: Outcome,
+    #[serde(with = "secs")]
+    pub queued: Duration,
+    #[serde(default)]
+    pub sequence: usize, // the position of the job in the order jobs were queued, from 0
+    #[serde(with = "secs::option")]
+    pub start: Option<Duration>,
+    #[serde(with = "secs::option")]
+    pub end: Option<Duration>,
+    #[serde(with = "secs")]
+    pub rate_limit_wait: Duration, // time spent waiting on the task's ratelimiter
+    #[serde(with = "secs")]
+    pub slot_wait: Duration, // time spent waiting for a concurrency slot
+    #[serde(default, with = "secs::option", skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub waits: Vec<Wait>, // every span of the two waits above, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retries: Vec<Attempt>, // the failed attempts before the last one, `start` and `end` are the last one's
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attempt {
+    #[serde(with = "secs")]
+    pub start: Duration,
+    #[serde(with = "secs")]
+    pub end: Duration,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Wait {
+    pub phase: Phase, // `WaitingRateLimit` or `WaitingSlot`
+    #[serde(with = "secs")]
+    pub start: Duration,
+    #[serde(with = "secs")]
+    pub end: Duration,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    pub tasks: BTreeMap<usize, TaskResult>,
+}
+
+impl Report {
+    // from the first task queued to the last one ending
+    pub fn makespan(&self) -> Duration {
+        let first = self.tasks.values().map(|t| t.queued).min().unwrap_or_default();
+        let last = self.tasks.values().filter_map(|t| t.end).max().unwrap_or(first);
+        last.saturating_sub(first)
+    }
+
+    // the tasks that were never started and why
+    pub fn not_run(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.tasks.iter().filter_map(|(&id, t)| match &t.outcome {
+            Outcome::Skipped { reason } => Some((id, reason.as_str())),
+            Outcome::Pending if t.start.is_none() => Some((id, "never started")),
+            _ => None,
+        })
+    }
+
+    // the tasks with a deadline that didn't finish by then, or not at all
+    pub fn missed_deadlines(&self) -> impl Iterator<Item = (usize, Duration)> {
+        self.tasks.iter().filter_map(|(&id, t)| match (t.deadline, &t.outcome) {
+            (Some(deadline), Outcome::Finished { .. }) if t.end.is_some_and(|end| end <= deadline) => None,
+            (Some(deadline), _) => Some((id, deadline)),
+            (None, _) => None,
+        })
+    }
+
+    // the tasks that started but have no outcome, because their session was aborted
+    pub fn in_flight(&self) -> impl Iterator<Item = usize> {
+        self.tasks.iter().filter(|(_, t)| t.outcome == Outcome::Pending && t.start.is_some()).map(|(&id, _)| id)
+    }
+
+    pub fn print(&self) {
+        for (id, t) in &self.tasks {
+            let time = |at: Option<Duration>| at.map_or(String::from("-"), |at| format!("{:.3}s", at.as_secs_f64()));
+            let outcome = match &t.outcome {
+                Outcome::Pending if t.start.is_some() => String::from("aborted"),
+                Outcome::Pending => String::from("not run"),
+                Outcome::Finished { output } => output.clone(),
+                Outcome::Failed { error } => format!("failed : {error}"),
+                Outcome::TimedOut { after } => format!("timed out after {:.3}s", after.as_secs_f64()),
+                Outcome::Skipped { reason } => format!("skipped : {reason}"),
+            };
+            let outcome = match t.retries.len() {
+                0 => outcome,
+                n => format!("{outcome} (after {n} retries)"),
+            };
+            let outcome = match self.missed_deadlines().any(|(missed, _)| missed == *id) {
+                true => format!("{outcome}, missed its deadline of {:.3}s", t.deadline.unwrap_or_default().as_secs_f64()),
+                false => outcome,
+            };
+            println!("{id:>4} {:<5} {:<18} {:>8} -> {:>8}  ratelimit {:>6.3}s  slot {:>6.3}s  {outcome}",
+                t.robot, t.task, time(t.start), time(t.end), t.rate_limit_wait.as_secs_f64(), t.slot_wait.as_secs_f64());
+        }
+        println!("makespan : {:.3}s", self.makespan().as_secs_f64());
+        let mut not_run: Vec<(&str, Vec<String>)> = Vec::new(); // grouped by reason
+        for (id, reason) in self.not_run() {
+            match not_run.iter_mut().find(|(r, _)| *r == reason) {
+                Some((_, ids)) => ids.push(id.to_string()),
+                None => not_run.push((reason, vec![id.to_string()])),
+            }
+        }
+        for (reason, ids) in not_run {
+            println!("not run, {reason} : {}", ids.join(", "));
+        }
+        let missed: Vec<String> = self.missed_deadlines().map(|(id, _)| id.to_string()).collect();
+        if !missed.is_empty() {
+            println!("missed deadlines : {}", missed.join(", "));
+        }
+        let in_flight: Vec<String> = self.in_flight().map(|id| id.to_string()).collect();
+        if !in_flight.is_empty() {
+            println!("in flight when aborted : {}", in_flight.join(", "));
+        }
+    }
+}
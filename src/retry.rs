@@ -0,0 +1,97 @@
+// how a task type is retried when its handler fails, the retries go through the ratelimiter and
+// the concurrency limit like any other start, and the robot runs nothing else in the meantime
+use std::sync::Arc;
+use std::time::Duration;
+use crate::task::TaskError;
+
+type Predicate = Arc<dyn Fn(&TaskError) -> bool + Send + Sync>;
+
+// the n-th retry waits `initial * multiplier^(n-1)`, capped at `max`, then shortened by up to
+// `jitter` of itself so robots failing together don't all come back at the same instant
+#[derive(Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32, // counting the first one, 1 never retries
+    pub initial: Duration,
+    pub max: Duration,
+    pub multiplier: f64,
+    pub jitter: f64, // between 0 and 1
+    retryable: Option<Predicate>, // every error when none
+}
+
+impl RetryPolicy {
+    pub fn attempts(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.5,
+            retryable: None,
+        }
+    }
+
+    pub fn backoff(self, initial: Duration, max: Duration) -> Self {
+        Self { initial, max, ..self }
+    }
+
+    pub fn multiplier(self, multiplier: f64) -> Self {
+        Self { multiplier, ..self }
+    }
+
+    pub fn jitter(self, jitter: f64) -> Self {
+        Self { jitter, ..self }
+    }
+
+    // only the errors for which `retryable` is true are retried
+    pub fn retry_if(self, retryable: impl Fn(&TaskError) -> bool + Send + Sync + 'static) -> Self {
+        Self { retryable: Some(Arc::new(retryable)), ..self }
+    }
+
+    pub(crate) fn check(&self) -> Result<(), String> {
+        if self.max_attempts == 0 {
+            return Err("needs at least 1 attempt".into());
+        }
+        if !(self.multiplier.is_finite() && self.multiplier >= 1.0) {
+            return Err(format!("the backoff multiplier must be at least 1, got {}", self.multiplier));
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err(format!("jitter must be between 0 and 1, got {}", self.jitter));
+        }
+        Ok(())
+    }
+
+    // how long to wait before the next attempt after `attempt` failed with `error`, none when the
+    // task shouldn't be retried
+    //
+    // the jitter is derived from the task id and the attempt instead of a random source, so a run
+    // can be replayed exactly
+    pub fn delay(&self, task_id: usize, attempt: u32, error: &TaskError) -> Option<Duration> {
+        if attempt >= self.max_attempts || self.retryable.as_ref().is_some_and(|retryable| !retryable(error)) {
+            return None;
+        }
+        let backoff = self.initial.as_secs_f64() * self.multiplier.powi(attempt as i32 - 1);
+        let backoff = backoff.min(self.max.as_secs_f64());
+        let random = splitmix((task_id as u64) << 32 | attempt as u64) as f64 / u64::MAX as f64;
+        Some(Duration::from_secs_f64(backoff * (1.0 - self.jitter * random)))
+    }
+}
+
+impl std::fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial", &self.initial)
+            .field("max", &self.max)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .field("retryable", &self.retryable.as_ref().map(|_| "custom"))
+            .finish()
+    }
+}
+
+fn splitmix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
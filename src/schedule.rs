@@ -0,0 +1,61 @@
+// planning types shared by the solvers, all times are offsets from the start of the run
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::{Job, Rate};
+
+#[derive(Debug, Clone, Copy)]
+pub struct TaskSpec {
+    pub duration: Duration, // how long the task runs once started
+    pub interval: Duration, // minimum time between two starts of this task type
+    pub burst: u32, // starts that can happen back to back before the interval applies
+}
+
+impl TaskSpec {
+    pub fn rate(&self) -> Rate {
+        Rate::every(self.interval).burst(self.burst)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub id: usize,
+    pub robot: String,
+    pub task: String,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Schedule {
+    pub entries: Vec<Entry>, // sorted by start time
+    pub makespan: Duration,
+}
+
+impl Schedule {
+    pub fn new(mut entries: Vec<Entry>) -> Self {
+        entries.sort_by_key(|e| (e.start, e.id));
+        let makespan = entries.iter().map(|e| e.end).max().unwrap_or_default();
+        Self { entries, makespan }
+    }
+
+    pub fn robot<'a>(&'a self, robot: &'a str) -> impl Iterator<Item = &'a Entry> + 'a {
+        self.entries.iter().filter(move |e| e.robot == robot)
+    }
+
+    // the jobs with a deadline that the schedule ends after, or doesn't run, with when they end
+    pub fn missed_deadlines(&self, jobs: &[Job]) -> Vec<(usize, Duration, Option<Duration>)> {
+        let ends: HashMap<usize, Duration> = self.entries.iter().map(|e| (e.id, e.end)).collect();
+        jobs.iter()
+            .filter_map(|job| Some((job.id, job.deadline?, ends.get(&job.id).copied())))
+            .filter(|&(_, deadline, end)| end.is_none_or(|end| end > deadline))
+            .collect()
+    }
+
+    pub fn print(&self) {
+        for e in &self.entries {
+            println!("{:>8.3}s -> {:>8.3}s  {:<5} {:<18} id {}",
+                e.start.as_secs_f64(), e.end.as_secs_f64(), e.robot, e.task, e.id);
+        }
+        println!("makespan : {:.3}s", self.makespan.as_secs_f64());
+    }
+}
@@ -0,0 +1,158 @@
+// virtual time simulation of a run, nothing sleeps so a whole run is planned in microseconds
+//
+// the task durations are taken from the specs instead of actually running the tasks, everything
+// else mirrors what the real schedulers do
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use crate::graph;
+use crate::job::{due, urgency};
+use crate::optimized::{Planner, Step};
+use crate::schedule::{Entry, Schedule, TaskSpec};
+use crate::{Error, Job, Strategy};
+
+// `aging` is the one of the executor, `DEFAULT_AGING` unless set
+pub fn simulate(jobs: &[Job], specs: &HashMap<String, TaskSpec>, concurrency: usize, aging: Duration, strategy: Strategy) -> Result<Schedule, Error> {
+    if concurrency == 0 {
+        return Err(Error::InvalidConcurrency);
+    }
+    if aging.is_zero() {
+        return Err(Error::InvalidAging);
+    }
+    if let Some(job) = jobs.iter().find(|job| !specs.contains_key(&job.task)) {
+        return Err(Error::UnknownTask(job.task.clone()));
+    }
+    // jobs waiting on each other would never start
+    graph::check(jobs)?;
+    Ok(match strategy {
+        Strategy::Idiomatic => idiomatic(jobs, specs, concurrency, aging),
+        Strategy::Optimized => optimized(jobs, specs, concurrency, aging),
+    })
+}
+
+enum State {
+    Blocked, // on a job of another robot
+    Waiting(Duration), // since when, the most urgent then oldest waiter is let in first
+    Running(Duration),
+    Done,
+}
+
+// every robot waits for its next task to get a concurrency slot and a token at the same instant,
+// by priority then in the order the robots started waiting, a task whose ratelimiter is empty
+// doesn't hold back the ones behind it, a robot whose next task depends on a job of another robot
+// only starts waiting once that job is over
+fn idiomatic(jobs: &[Job], specs: &HashMap<String, TaskSpec>, concurrency: usize, aging: Duration) -> Schedule {
+    let mut names: Vec<&str> = Vec::new();
+    let mut queues: Vec<VecDeque<&Job>> = Vec::new();
+    for job in jobs {
+        let r = names.iter().position(|&n| n == job.robot).unwrap_or_else(|| {
+            names.push(&job.robot);
+            queues.push(VecDeque::new());
+            names.len() - 1
+        });
+        queues[r].push_back(job);
+    }
+
+    let mut ended: HashSet<usize> = HashSet::new();
+    let mut states: Vec<State> = queues.iter()
+        .map(|q| if q.is_empty() { State::Done } else { State::Blocked })
+        .collect();
+    let mut tats: HashMap<&str, Duration> = HashMap::new(); // theoretical arrival time per task type
+    let mut running = 0;
+    let mut entries = Vec::new();
+    let mut now = Duration::ZERO;
+
+    loop {
+        for r in 0..states.len() {
+            if matches!(states[r], State::Running(end) if end <= now) {
+                running -= 1;
+                ended.extend(queues[r].pop_front().map(|job| job.id));
+                states[r] = if queues[r].is_empty() { State::Done } else { State::Blocked };
+            }
+        }
+        for r in 0..states.len() {
+            let ready = |job: &Job| job.after.iter().all(|id| ended.contains(id));
+            if matches!(states[r], State::Blocked) && ready(queues[r][0]) {
+                states[r] = State::Waiting(now);
+            }
+        }
+
+        let waiting: Vec<(Duration, usize)> = states.iter().enumerate()
+            .filter_map(|(r, s)| match s {
+                State::Waiting(since) => Some((*since, r)),
+                _ => None,
+            })
+            .collect();
+        let top = waiting.iter().map(|&(_, r)| queues[r][0].priority).max().unwrap_or_default();
+        let mut order: Vec<(i32, Duration, Duration, usize)> = waiting.into_iter()
+            .map(|(since, r)| (-urgency(queues[r][0].priority, now - since, aging, top), due(queues[r][0].deadline), since, r))
+            .collect();
+        order.sort();
+        for (_, _, _, r) in order {
+            if running >= concurrency {
+                break;
+            }
+            let Job { id, task, .. } = queues[r][0];
+            let (id, task) = (*id, task.as_str());
+            let spec = &specs[task];
+            let tat = tats.get(task).copied().unwrap_or_default();
+            if spec.rate().available_at(tat) > now {
+                continue;
+            }
+            tats.insert(task, spec.rate().started(tat, now));
+            running += 1;
+            states[r] = State::Running(now + spec.duration);
+            entries.push(Entry { id, robot: names[r].into(), task: task.into(), start: now, end: now + spec.duration });
+        }
+
+        let next_end = states.iter().filter_map(|s| match s {
+            State::Running(end) => Some(*end),
+            _ => None,
+        });
+        let next_token = states.iter().enumerate().filter_map(|(r, s)| match s {
+            State::Waiting(_) => {
+                let task = queues[r][0].task.as_str();
+                tats.get(task).map(|&tat| specs[task].rate().available_at(tat)).filter(|&t| t > now)
+            }
+            _ => None,
+        });
+        match next_end.chain(next_token).min() {
+            Some(next) => now = next,
+            None => break,
+        }
+    }
+    Schedule::new(entries)
+}
+
+// drives the same planner as the optimized executor with a virtual clock
+fn optimized(jobs: &[Job], specs: &HashMap<String, TaskSpec>, concurrency: usize, aging: Duration) -> Schedule {
+    let rates = specs.iter().map(|(task, spec)| (task.clone(), spec.rate())).collect();
+    let mut planner = Planner::new(rates, concurrency, aging);
+    for job in jobs {
+        planner.push(job.clone());
+    }
+    planner.seal();
+    let mut running: Vec<(Duration, usize)> = Vec::new();
+    let mut entries = Vec::new();
+    let mut now = Duration::ZERO;
+
+    loop {
+        match planner.poll(now) {
+            Step::Start { robot, job } => {
+                let end = now + specs[&job.task].duration;
+                running.push((end, robot));
+                entries.push(Entry { id: job.id, robot: job.robot, task: job.task, start: now, end });
+            }
+            Step::Wait(until) => {
+                now = running.iter().map(|&(end, _)| end).chain(until).min().expect("planner waits on something");
+                running.retain(|&(end, robot)| {
+                    if end <= now {
+                        planner.finish(robot);
+                    }
+                    end > now
+                });
+            }
+            Step::Done => break,
+        }
+    }
+    Schedule::new(entries)
+}
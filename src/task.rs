@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub type Output = String;
+pub type TaskError = Box<dyn std::error::Error + Send + Sync>;
+
+// a chore a robot can perform, register it in a `Registry` under the name jobs refer to
+#[async_trait::async_trait]
+pub trait TaskHandler: Send + Sync {
+    async fn run(&self, task_id: usize, robot: &str) -> Result<Output, TaskError>;
+}
+
+// plain async functions and closures are handlers too
+#[async_trait::async_trait]
+impl<F, Fut> TaskHandler for F
+where
+    F: Fn(usize, String) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Output, TaskError>> + Send,
+{
+    async fn run(&self, task_id: usize, robot: &str) -> Result<Output, TaskError> {
+        self(task_id, robot.to_owned()).await
+    }
+}
+
+// a task that just takes some time on the executor's clock and returns a fixed output
+pub struct Simulated {
+    pub duration: Duration,
+    pub output: Output,
+}
+
+#[async_trait::async_trait]
+impl TaskHandler for Simulated {
+    async fn run(&self, _task_id: usize, _robot: &str) -> Result<Output, TaskError> {
+        crate::clock::sleep(self.duration).await;
+        Ok(self.output.clone())
+    }
+}
+
+// the task may only be started once every interval, `burst` starts can happen back to back
+// before the interval applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    pub interval: Duration,
+    pub burst: u32,
+}
+
+impl Rate {
+    pub fn every(interval: Duration) -> Self {
+        Self { interval, burst: 1 }
+    }
+
+    pub fn burst(self, burst: u32) -> Self {
+        Self { burst, ..self }
+    }
+
+    // how far ahead of a start its theoretical arrival time may be, the ratelimiters replay the
+    // cell rate algorithm so `burst` starts fit in at once before the interval applies
+    pub(crate) fn tolerance(&self) -> Duration {
+        self.interval * self.burst.max(1).saturating_sub(1)
+    }
+
+    // when the next start is allowed, given the theoretical arrival time `tat`
+    pub(crate) fn available_at(&self, tat: Duration) -> Duration {
+        tat.saturating_sub(self.tolerance())
+    }
+
+    // the theoretical arrival time once a task starts at `now`
+    pub(crate) fn started(&self, tat: Duration, now: Duration) -> Duration {
+        tat.max(now) + self.interval
+    }
+}
+
+impl From<Duration> for Rate {
+    fn from(interval: Duration) -> Self {
+        Self::every(interval)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Registered {
+    pub(crate) handler: Arc<dyn TaskHandler>,
+    pub(crate) rate: Rate,
+}
+
+// every task type the executor knows about, keyed by the name used in jobs
+#[derive(Clone, Default)]
+pub struct Registry {
+    tasks: HashMap<String, Registered>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // registering the same name twice replaces the previous handler
+    pub fn register(&mut self, name: impl Into<String>, rate: impl Into<Rate>, handler: impl TaskHandler + 'static) -> &mut Self {
+        self.tasks.insert(name.into(), Registered { handler: Arc::new(handler), rate: rate.into() });
+        self
+    }
+
+    // adds every task type of `other`, replacing the ones with the same name
+    pub fn merge(&mut self, other: Registry) -> &mut Self {
+        self.tasks.extend(other.tasks);
+        self
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tasks.contains_key(name)
+    }
+
+    pub fn rate(&self, name: &str) -> Option<Rate> {
+        self.tasks.get(name).map(|t| t.rate)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tasks.keys().map(String::as_str)
+    }
+
+    pub(crate) fn into_inner(self) -> HashMap<String, Registered> {
+        self.tasks
+    }
+}
@@ -0,0 +1,105 @@
+// a run in the trace event format, to be opened with chrome://tracing or https://ui.perfetto.dev
+//
+// every robot is a thread, its tasks are complete events and the time it spent waiting on a
+// ratelimiter or on a concurrency slot are slices of their own category, counters track the
+// tasks in flight against the concurrency limit and when every ratelimiter has a token again
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use serde_json::{Value, json};
+use crate::event::Phase;
+use crate::report::{Outcome, Report};
+use crate::verify::Limits;
+
+const PID: u32 = 1;
+
+fn micros(at: Duration) -> f64 {
+    at.as_secs_f64() * 1e6
+}
+
+pub fn trace(report: &Report, limits: &Limits) -> Value {
+    let mut events = vec![json!({ "name": "process_name", "ph": "M", "pid": PID, "args": { "name": "executor" } })];
+
+    // robots in the order of their lowest id, the report is sorted by id
+    let mut threads: HashMap<&str, usize> = HashMap::new();
+    for t in report.tasks.values() {
+        if !threads.contains_key(t.robot.as_str()) {
+            let tid = threads.len() + 1;
+            threads.insert(&t.robot, tid);
+            events.push(json!({ "name": "thread_name", "ph": "M", "pid": PID, "tid": tid, "args": { "name": t.robot } }));
+            events.push(json!({ "name": "thread_sort_index", "ph": "M", "pid": PID, "tid": tid, "args": { "sort_index": tid } }));
+        }
+    }
+
+    let last = report.tasks.values().filter_map(|t| t.end.or(t.start)).max().unwrap_or_default();
+    let mut in_flight: BTreeMap<Duration, i64> = BTreeMap::new();
+    let mut starts: BTreeMap<&str, Vec<Duration>> = BTreeMap::new();
+    for (&id, t) in &report.tasks {
+        let tid = threads[t.robot.as_str()];
+        for wait in &t.waits {
+            let (name, cat) = match wait.phase {
+                Phase::WaitingRateLimit => (format!("ratelimit {}", t.task), "rate_limit"),
+                _ => (format!("slot {}", t.task), "slot"),
+            };
+            events.push(json!({
+                "name": name, "cat": cat, "ph": "X", "pid": PID, "tid": tid,
+                "ts": micros(wait.start), "dur": micros(wait.end - wait.start), "args": { "id": id },
+            }));
+        }
+        for attempt in &t.retries {
+            events.push(json!({
+                "name": t.task, "cat": "task", "ph": "X", "pid": PID, "tid": tid,
+                "ts": micros(attempt.start), "dur": micros(attempt.end - attempt.start),
+                "args": { "id": id, "status": "retried", "result": attempt.error },
+            }));
+            *in_flight.entry(attempt.start).or_default() += 1;
+            *in_flight.entry(attempt.end).or_default() -= 1;
+            starts.entry(&t.task).or_default().push(attempt.start);
+        }
+        let Some(start) = t.start else { continue };
+        let end = t.end.unwrap_or(last);
+        let (status, result) = match &t.outcome {
+            Outcome::Pending => ("running", Value::Null),
+            Outcome::Finished { output } => ("finished", json!(output)),
+            Outcome::Failed { error } => ("failed", json!(error)),
+            Outcome::TimedOut { after } => ("timed_out", json!(after.as_secs_f64())),
+            Outcome::Skipped { reason } => ("skipped", json!(reason)),
+        };
+        events.push(json!({
+            "name": t.task, "cat": "task", "ph": "X", "pid": PID, "tid": tid,
+            "ts": micros(start), "dur": micros(end - start),
+            "args": { "id": id, "status": status, "result": result },
+        }));
+        *in_flight.entry(start).or_default() += 1;
+        *in_flight.entry(end).or_default() -= 1;
+        starts.entry(&t.task).or_default().push(start);
+    }
+
+    // the limit on its own track next to it, chrome stacks the series of a single counter
+    for at in [Duration::ZERO, last] {
+        events.push(json!({ "name": "concurrency limit", "ph": "C", "pid": PID, "ts": micros(at), "args": { "tasks": limits.concurrency } }));
+    }
+    let mut running = 0;
+    for (at, change) in in_flight {
+        running += change;
+        events.push(json!({ "name": "in flight", "ph": "C", "pid": PID, "ts": micros(at), "args": { "tasks": running } }));
+    }
+
+    // the theoretical arrival time of the cell rate algorithm, less the burst allowance
+    for (task, mut starts) in starts {
+        let Some(rate) = limits.rates.get(task) else { continue };
+        let tau = rate.interval * rate.burst.saturating_sub(1);
+        starts.sort();
+        let mut tat = Duration::ZERO;
+        events.push(json!({ "name": format!("{task} available at"), "ph": "C", "pid": PID, "ts": 0.0, "args": { "seconds": 0.0 } }));
+        for start in starts {
+            tat = tat.max(start) + rate.interval;
+            let available = tat.saturating_sub(tau).max(start);
+            events.push(json!({
+                "name": format!("{task} available at"), "ph": "C", "pid": PID, "ts": micros(start),
+                "args": { "seconds": available.as_secs_f64() },
+            }));
+        }
+    }
+
+    json!({ "traceEvents": events, "displayTimeUnit": "ms" })
+}
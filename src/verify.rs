@@ -0,0 +1,208 @@
+// checks a recorded run against the constraints of the assignment, no more tasks in parallel than
+// the concurrency limit, one task at a time per robot, the ratelimit of every task type, and every
+// robot running its tasks in the order they were queued
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::time::Duration;
+use crate::event::Phase;
+use crate::schedule::Entry;
+use crate::{Error, ExecutorEvent, Rate, Report};
+
+// timestamps are taken a moment after the fact, small overlaps below this are not violations
+pub const DEFAULT_TOLERANCE: Duration = Duration::from_millis(1);
+
+#[derive(Debug, Clone)]
+pub struct Limits {
+    pub concurrency: usize,
+    pub rates: HashMap<String, Rate>,
+    pub tolerance: Duration,
+}
+
+// the tasks that started, tasks still running when the log ends are running until its last timestamp
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    pub entries: Vec<Entry>,
+    pub order: Vec<usize>, // ids in the order they were queued
+}
+
+impl Timeline {
+    pub fn from_events<'a>(events: impl IntoIterator<Item = &'a ExecutorEvent>) -> Self {
+        let mut order = Vec::new();
+        let mut started: Vec<Entry> = Vec::new();
+        let mut running = HashMap::new(); // index of the current attempt of every started task
+        let mut last = Duration::ZERO;
+        for event in events {
+            last = last.max(event.at());
+            let ExecutorEvent::Task { at, robot, id, task, phase, .. } = event else { continue };
+            match phase {
+                Phase::Queued => order.push(*id),
+                Phase::Started => {
+                    running.insert(*id, started.len());
+                    started.push(Entry { id: *id, robot: robot.clone(), task: task.clone(), start: *at, end: Duration::MAX });
+                }
+                // a retried attempt ends like any other
+                Phase::Finished | Phase::Failed | Phase::Retrying | Phase::TimedOut => {
+                    if let Some(i) = running.remove(id) {
+                        started[i].end = *at;
+                    }
+                }
+                _ => {}
+            }
+        }
+        for &i in running.values() {
+            started[i].end = last;
+        }
+        Self { entries: started, order }
+    }
+
+    // in the order the jobs were queued, reports without a sequence take tasks queued at the same
+    // time in the order of their ids
+    pub fn from_report(report: &Report) -> Self {
+        let mut queued: Vec<(Duration, usize, usize)> = report.tasks.iter().map(|(&id, t)| (t.queued, t.sequence, id)).collect();
+        queued.sort();
+        let last = report.tasks.values().filter_map(|t| t.end.or(t.start)).max().unwrap_or_default();
+        let mut entries = Vec::new();
+        for (&id, t) in &report.tasks {
+            let entry = |start, end| Entry { id, robot: t.robot.clone(), task: t.task.clone(), start, end };
+            entries.extend(t.retries.iter().map(|attempt| entry(attempt.start, attempt.end)));
+            if let Some(start) = t.start {
+                entries.push(entry(start, t.end.unwrap_or(last)));
+            }
+        }
+        Self { entries, order: queued.into_iter().map(|(_, _, id)| id).collect() }
+    }
+
+    // a json report, or the json lines event stream of `--json`
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        if let Ok(report) = serde_json::from_str::<Report>(input) {
+            return Ok(Self::from_report(&report));
+        }
+        let mut events = Vec::new();
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let event = serde_json::from_str(line).map_err(|e| Error::Parse { line: i + 1, message: e.to_string() })?;
+            events.push(event);
+        }
+        Ok(Self::from_events(&events))
+    }
+
+    // `-` reads from stdin
+    pub fn read(path: &str) -> Result<Self, Error> {
+        let input = if path == "-" {
+            std::io::read_to_string(std::io::stdin())
+        } else {
+            std::fs::read_to_string(path)
+        };
+        Self::parse(&input.map_err(|e| Error::Io(format!("{path} : {e}")))?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    Concurrency { at: Duration, running: Vec<usize>, limit: usize },
+    RobotOverlap { robot: String, first: usize, second: usize, at: Duration, first_end: Duration },
+    // `previous` is the start the ratelimit is counted from, `burst` starts before this one
+    RateLimit { task: String, id: usize, at: Duration, previous: usize, previous_at: Duration, rate: Rate },
+    Order { robot: String, id: usize, at: Duration, queued_before: usize, queued_before_at: Duration },
+    UnknownTask { task: String, id: usize },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let secs = |at: &Duration| at.as_secs_f64();
+        match self {
+            Violation::Concurrency { at, running, limit } => write!(f,
+                "{:.3}s : {} tasks running at once, the limit is {limit}, ids {running:?}", secs(at), running.len()),
+            Violation::RobotOverlap { robot, first, second, at, first_end } => write!(f,
+                "{:.3}s : {robot} started id {second} while id {first} was running until {:.3}s", secs(at), secs(first_end)),
+            Violation::RateLimit { task, id, at, previous, previous_at, rate } => write!(f,
+                "{:.3}s : id {id} started {task} {:.3}s after id {previous} at {:.3}s, the ratelimit allows {} every {:.3}s",
+                secs(at), secs(&at.saturating_sub(*previous_at)), secs(previous_at), rate.burst, secs(&(rate.interval * rate.burst))),
+            Violation::Order { robot, id, at, queued_before, queued_before_at } => write!(f,
+                "{:.3}s : {robot} started id {id} before id {queued_before} which was queued first and started at {:.3}s",
+                secs(at), secs(queued_before_at)),
+            Violation::UnknownTask { task, id } => write!(f, "id {id} : no ratelimit known for task {task}"),
+        }
+    }
+}
+
+// every violation found, sorted by time, empty when the run obeyed all the constraints
+pub fn verify(timeline: &Timeline, limits: &Limits) -> Vec<Violation> {
+    let mut entries: Vec<&Entry> = timeline.entries.iter().collect();
+    entries.sort_by_key(|e| (e.start, e.id));
+    let tolerance = limits.tolerance;
+    let mut violations = Vec::new();
+
+    // concurrency, a task ending at the instant another one starts frees its slot in time
+    let mut running: BTreeSet<(Duration, usize)> = BTreeSet::new();
+    for e in &entries {
+        while running.first().is_some_and(|&(end, _)| end <= e.start + tolerance) {
+            running.pop_first();
+        }
+        running.insert((e.end, e.id));
+        if running.len() > limits.concurrency {
+            let mut ids: Vec<usize> = running.iter().map(|&(_, id)| id).collect();
+            ids.sort();
+            violations.push(Violation::Concurrency { at: e.start, running: ids, limit: limits.concurrency });
+        }
+    }
+
+    // one task at a time per robot, in the order they were queued
+    let position: HashMap<usize, usize> = timeline.order.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+    let mut robots: HashMap<&str, Vec<&Entry>> = HashMap::new();
+    for e in &entries {
+        robots.entry(&e.robot).or_default().push(e);
+    }
+    for (robot, tasks) in &robots {
+        for pair in tasks.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if b.start + tolerance < a.end {
+                violations.push(Violation::RobotOverlap {
+                    robot: robot.to_string(), first: a.id, second: b.id, at: b.start, first_end: a.end,
+                });
+            }
+            if position.get(&b.id) < position.get(&a.id) && position.contains_key(&b.id) {
+                violations.push(Violation::Order {
+                    robot: robot.to_string(), id: a.id, at: a.start, queued_before: b.id, queued_before_at: b.start,
+                });
+            }
+        }
+    }
+
+    // ratelimits, replaying the cell rate algorithm the limiters use
+    let mut types: HashMap<&str, Vec<&Entry>> = HashMap::new();
+    for e in &entries {
+        types.entry(&e.task).or_default().push(e);
+    }
+    for (task, starts) in &types {
+        let Some(&rate) = limits.rates.get(*task) else {
+            violations.extend(starts.iter().map(|e| Violation::UnknownTask { task: task.to_string(), id: e.id }));
+            continue;
+        };
+        let burst = rate.burst.max(1) as usize;
+        let tau = rate.interval * (burst as u32 - 1);
+        let mut tat = starts.first().map(|e| e.start).unwrap_or_default(); // theoretical arrival time
+        for (i, e) in starts.iter().enumerate() {
+            if e.start + tau + tolerance < tat {
+                let previous = starts[i.saturating_sub(burst)];
+                violations.push(Violation::RateLimit {
+                    task: task.to_string(), id: e.id, at: e.start, previous: previous.id, previous_at: previous.start, rate,
+                });
+                // counted from an empty bucket, so a single early start isn't blamed on all the next ones
+                tat = e.start + tau + rate.interval;
+            } else {
+                tat = tat.max(e.start) + rate.interval;
+            }
+        }
+    }
+
+    violations.sort_by_key(|v| match v {
+        Violation::Concurrency { at, .. } | Violation::RobotOverlap { at, .. }
+        | Violation::RateLimit { at, .. } | Violation::Order { at, .. } => *at,
+        Violation::UnknownTask { .. } => Duration::ZERO,
+    });
+    violations
+}
@@ -0,0 +1,35 @@
+// the household the integration tests share, every chore takes its time on the executor's clock so
+// the tests run on tokio's paused one
+#![allow(dead_code)] // not every test file uses every fixture
+use std::time::Duration;
+use futures::future::BoxFuture;
+use robot_tech_test::{Executor, ExecutorBuilder, Strategy, TaskError, clock};
+
+pub const STRATEGIES: [Strategy; 2] = [Strategy::Idiomatic, Strategy::Optimized];
+
+pub fn secs(secs: f64) -> Duration {
+    Duration::from_secs_f64(secs)
+}
+
+// a chore taking `millis` milliseconds that always ends with `output`
+pub fn chore(millis: u64, output: &'static str) -> impl Fn(usize, String) -> BoxFuture<'static, Result<String, TaskError>> + Send + Sync {
+    move |_, _| Box::pin(async move {
+        clock::sleep(Duration::from_millis(millis)).await;
+        Ok(String::from(output))
+    })
+}
+
+// a chore that never returns
+pub fn hang(_task_id: usize, _robot: String) -> std::future::Pending<Result<String, TaskError>> {
+    std::future::pending()
+}
+
+// the robots feed the cat once every 2 seconds in 0.5 seconds, and water the plants once every 3
+// seconds in 0.7 seconds, without printing anything
+pub fn household(robots: &[&str]) -> ExecutorBuilder {
+    Executor::builder()
+        .robots(robots.iter().copied())
+        .task("feed_the_cat", Duration::from_secs(2), chore(500, "Meow"))
+        .task("water_the_plants", Duration::from_secs(3), chore(700, "Blub"))
+        .quiet()
+}
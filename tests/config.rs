@@ -0,0 +1,88 @@
+// configs are rejected up front with a message, rather than panicking once the executor is built
+use robot_tech_test::Error;
+use robot_tech_test::config::Config;
+
+const VALID: &str = r#"
+    concurrency = 2
+    robots = ["Dave", "Cris"]
+
+    [tasks.feed_the_cat]
+    duration = 0.5
+    interval = 2.0
+"#;
+
+// the config above with `from` replaced by `to`
+fn with(from: &str, to: &str) -> Result<Config, Error> {
+    assert!(VALID.contains(from), "{from:?} isn't in the config");
+    Config::from_toml(&VALID.replacen(from, to, 1))
+}
+
+fn rejected(config: Result<Config, Error>, message: &str) {
+    match config {
+        Err(Error::Config(error)) => assert!(error.contains(message), "{error:?} doesn't say {message:?}"),
+        Err(error) => panic!("rejected for another reason : {error}"),
+        Ok(_) => panic!("accepted, expected {message:?}"),
+    }
+}
+
+#[test]
+fn a_valid_config_builds() {
+    let config = Config::from_toml(VALID).unwrap();
+    assert!(config.builder().quiet().build().is_ok());
+}
+
+#[test]
+fn rates_need_an_interval_or_a_quota() {
+    rejected(with("interval = 2.0", "interval = 0.0"), "interval must be");
+    rejected(with("interval = 2.0", "interval = 2.0\nquota = { count = 3, period = 60.0 }"), "either interval or quota");
+    rejected(with("interval = 2.0", ""), "needs an interval or a quota");
+    rejected(with("interval = 2.0", "quota = { count = 0, period = 60.0 }"), "quota needs");
+    rejected(with("interval = 2.0", "interval = 2.0\nburst = 0"), "burst");
+}
+
+#[test]
+fn seconds_must_fit_a_duration() {
+    rejected(with("interval = 2.0", "interval = 1e30"), "interval must be");
+    rejected(with("duration = 0.5", "duration = 1e30"), "duration must be");
+    rejected(with("duration = 0.5", "duration = -1.0"), "duration must be");
+    rejected(with("interval = 2.0", "quota = { count = 3, period = 1e30 }"), "quota needs");
+    rejected(with("interval = 2.0", "interval = 2.0\ntimeout = 1e30"), "timeout must be");
+    rejected(with("concurrency = 2", "concurrency = 2\naging = 1e30"), "aging must be");
+    rejected(with("concurrency = 2", "concurrency = 2\naging = 0.0"), "aging must be");
+    rejected(with("interval = 2.0", "interval = 2.0\nretry = { attempts = 3, backoff = 1e30 }"), "retry backoff must be");
+    rejected(with("interval = 2.0", "interval = 2.0\nretry = { attempts = 3, max_backoff = -1.0 }"), "retry max_backoff must be");
+}
+
+#[test]
+fn a_quota_must_leave_time_between_starts() {
+    rejected(with("interval = 2.0", "quota = { count = 4000000000, period = 1.0 }"), "too many starts");
+    let config = with("interval = 2.0", "quota = { count = 3, period = 60.0 }").unwrap();
+    assert!(config.builder().quiet().build().is_ok());
+}
+
+#[test]
+fn on_timeout_needs_a_timeout() {
+    rejected(with("interval = 2.0", "interval = 2.0\non_timeout = \"skip\""), "on_timeout needs a timeout");
+    assert!(with("interval = 2.0", "interval = 2.0\ntimeout = 5.0\non_timeout = \"skip\"").is_ok());
+}
+
+#[test]
+fn robots_are_declared_once() {
+    rejected(with(r#"["Dave", "Cris"]"#, r#"["Dave", "Dave"]"#), "declared twice");
+    rejected(with(r#"["Dave", "Cris"]"#, r#"["Dave", " "]"#), "can't be empty");
+    rejected(with(r#"["Dave", "Cris"]"#, "[]"), "at least one robot");
+    rejected(with("concurrency = 2", "concurrency = 0"), "concurrency");
+}
+
+#[test]
+fn per_robot_settings_name_declared_robots() {
+    rejected(with("concurrency = 2", "concurrency = 2\non_failure = { Phil = \"halt\" }"), "robot Phil which isn't declared");
+    rejected(with("concurrency = 2", "concurrency = 2\ncapabilities = { Phil = [\"feed_the_cat\"] }"), "robot Phil which isn't declared");
+    assert!(with("concurrency = 2", "concurrency = 2\non_failure = { Dave = \"halt\" }").is_ok());
+}
+
+#[test]
+fn json_is_validated_too() {
+    let json = r#"{"concurrency": 1, "robots": ["Dave"], "tasks": {"feed_the_cat": {"duration": 0.5, "interval": 1e30}}}"#;
+    rejected(Config::from_json(json), "interval must be");
+}
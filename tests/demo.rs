@@ -0,0 +1,136 @@
+// the 30 jobs of the demo on the household of the assignment, with tokio's clock paused the
+// ratelimiters and the simulated tasks all run on virtual time, so a 70 seconds run takes a few
+// milliseconds and always ends the same way
+use std::time::Duration;
+use robot_tech_test::config::Config;
+use robot_tech_test::gantt::{self, Chart};
+use robot_tech_test::verify::{self, Timeline};
+use robot_tech_test::{DEFAULT_AGING, Executor, Job, ManualClock, Report, Strategy, load, simulate, trace};
+
+const HOUSEHOLD: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/household.toml");
+const JOBS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/jobs.jsonl");
+
+fn household() -> Config {
+    Config::read(HOUSEHOLD).expect("household.toml is valid")
+}
+
+fn jobs() -> Vec<Job> {
+    load::read(JOBS).expect("jobs.jsonl is valid")
+}
+
+// must be called with the clock paused, the executor takes its epoch when built
+fn executor(strategy: Strategy) -> Executor {
+    household().builder().strategy(strategy).quiet().build().expect("valid executor")
+}
+
+async fn run(strategy: Strategy) -> Report {
+    executor(strategy).run(jobs()).await.expect("jobs are valid")
+}
+
+// the start and end of every task, as planned by the simulator
+fn assert_matches_simulation(report: &Report, strategy: Strategy) {
+    let config = household();
+    let schedule = simulate::simulate(&jobs(), &config.specs(), config.concurrency, DEFAULT_AGING, strategy).unwrap();
+    assert_eq!(report.tasks.len(), schedule.entries.len());
+    for entry in &schedule.entries {
+        let task = &report.tasks[&entry.id];
+        assert_eq!((task.start, task.end), (Some(entry.start), Some(entry.end)), "task {}", entry.id);
+    }
+}
+
+fn assert_obeys_constraints(executor: &Executor, report: &Report) {
+    let limits = verify::Limits { tolerance: Duration::ZERO, ..executor.limits() };
+    let violations = verify::verify(&Timeline::from_report(report), &limits);
+    assert!(violations.is_empty(), "{}", violations.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn optimized_reaches_the_lower_bound() {
+    let report = run(Strategy::Optimized).await;
+    // 15 window cleanings 5 seconds apart, the last one taking 0.3 seconds
+    assert_eq!(report.makespan(), Duration::from_millis(70_300));
+    assert_matches_simulation(&report, Strategy::Optimized);
+}
+
+#[tokio::test(start_paused = true)]
+async fn idiomatic_matches_the_simulation() {
+    let report = run(Strategy::Idiomatic).await;
+    assert_eq!(report.makespan(), Duration::from_millis(70_300));
+    assert_matches_simulation(&report, Strategy::Idiomatic);
+}
+
+#[tokio::test(start_paused = true)]
+async fn runs_obey_the_constraints() {
+    for strategy in [Strategy::Idiomatic, Strategy::Optimized] {
+        let executor = executor(strategy);
+        let report = executor.run(jobs()).await.unwrap();
+        assert_obeys_constraints(&executor, &report);
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn robots_keep_their_order() {
+    let jobs = jobs();
+    let report = run(Strategy::Optimized).await;
+    for robot in household().robots {
+        let starts: Vec<Duration> = jobs.iter()
+            .filter(|job| job.robot == robot)
+            .map(|job| report.tasks[&job.id].start.expect("every task ran"))
+            .collect();
+        assert!(starts.is_sorted(), "{robot} ran its tasks out of order : {starts:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn runs_are_deterministic() {
+    for strategy in [Strategy::Idiomatic, Strategy::Optimized] {
+        let first = run(strategy).await;
+        let second = run(strategy).await;
+        assert_eq!(first, second, "{strategy:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn gantt_has_a_lane_per_robot() {
+    let report = run(Strategy::Idiomatic).await;
+    let chart = Chart::from_report("idiomatic", &report);
+    let robots: Vec<&str> = chart.lanes.iter().map(|lane| lane.robot.as_str()).collect();
+    assert_eq!(robots, ["Dave", "Cris", "Andi", "Nick", "Phil", "Maxi"]);
+    assert_eq!(chart.lanes.iter().map(|lane| lane.bars.len()).sum::<usize>(), 30);
+    assert!(chart.lanes.iter().any(|lane| !lane.waits.is_empty()));
+    let svg = gantt::svg(&[chart]);
+    assert_eq!(svg.matches("<title>id ").count(), 30);
+}
+
+#[tokio::test(start_paused = true)]
+async fn trace_has_the_tasks_and_counters() {
+    let executor = executor(Strategy::Optimized);
+    let report = executor.run(jobs()).await.unwrap();
+    let trace = trace::trace(&report, &executor.limits());
+    let events = trace["traceEvents"].as_array().unwrap();
+    let named = |name: &'static str| events.iter().filter(move |e| e["name"] == name);
+    assert_eq!(events.iter().filter(|e| e["cat"] == "task").count(), 30);
+    assert_eq!(named("thread_name").count(), 6);
+    assert!(named("in flight").all(|e| e["args"]["tasks"].as_i64().unwrap() <= 3));
+    assert_eq!(named("clean_the_windows available at").next_back().unwrap()["args"]["seconds"], 75.0);
+}
+
+// no timer at all, the test moves the clock 100ms at a time once the executor has nothing left to
+// do at the current instant, every duration and interval of the household is a multiple of it
+#[tokio::test]
+async fn manual_clock_drives_the_run() {
+    for strategy in [Strategy::Idiomatic, Strategy::Optimized] {
+        let clock = ManualClock::new();
+        let executor = household().builder().strategy(strategy).clock(clock.clone()).quiet().build().unwrap();
+        let run = tokio::spawn(async move { executor.run(jobs()).await.unwrap() });
+        while !run.is_finished() {
+            for _ in 0..32 {
+                tokio::task::yield_now().await;
+            }
+            clock.advance(Duration::from_millis(100));
+        }
+        let report = run.await.unwrap();
+        assert_eq!(report.makespan(), Duration::from_millis(70_300));
+        assert_matches_simulation(&report, strategy);
+    }
+}
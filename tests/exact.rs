@@ -0,0 +1,73 @@
+// the exact solver, its optimum on small lists and what it hands back once its budget runs out
+use std::collections::HashMap;
+use std::time::Duration;
+use robot_tech_test::schedule::TaskSpec;
+use robot_tech_test::{DEFAULT_AGING, Error, Job, Strategy, exact, simulate};
+
+mod common;
+use common::secs;
+
+fn specs() -> HashMap<String, TaskSpec> {
+    HashMap::from([
+        ("feed_the_cat".to_owned(), TaskSpec { duration: secs(0.5), interval: secs(2.0), burst: 1 }),
+        ("water_the_plants".to_owned(), TaskSpec { duration: secs(0.7), interval: secs(3.0), burst: 1 }),
+        ("clean_the_windows".to_owned(), TaskSpec { duration: secs(1.0), interval: secs(0.1), burst: 1 }),
+    ])
+}
+
+// Dave's cleaning holds him up, so Cris feeds the cat first and Dave's turn comes once the cat can
+// be fed again
+#[test]
+fn a_small_list_is_solved_to_its_optimum() {
+    let jobs = [Job::new(1, "Dave", "clean_the_windows"), Job::new(2, "Dave", "feed_the_cat"), Job::new(3, "Cris", "feed_the_cat")];
+    let solution = exact::solve(&jobs, &specs(), 2, exact::Budget::default()).unwrap();
+    assert!(solution.optimal);
+    assert_eq!(solution.schedule.makespan, secs(2.5));
+    let mut starts: Vec<(usize, Duration)> = solution.schedule.entries.iter().map(|entry| (entry.id, entry.start)).collect();
+    starts.sort();
+    assert_eq!(starts, [(1, secs(0.0)), (2, secs(2.0)), (3, secs(0.0))]);
+}
+
+// the optimized planner takes 6.5s, the search finds a 5.2s schedule
+fn crowded() -> Vec<Job> {
+    [
+        ("Andi", "feed_the_cat"), ("Cris", "feed_the_cat"), ("Andi", "clean_the_windows"), ("Cris", "clean_the_windows"),
+        ("Andi", "clean_the_windows"), ("Dave", "feed_the_cat"), ("Cris", "water_the_plants"), ("Dave", "water_the_plants"),
+    ].into_iter().enumerate().map(|(id, (robot, task))| Job::new(id, robot, task)).collect()
+}
+
+#[test]
+fn the_search_proves_its_optimum() {
+    let solution = exact::solve(&crowded(), &specs(), 2, exact::Budget::default()).unwrap();
+    assert!(solution.optimal);
+    assert_eq!(solution.schedule.makespan, secs(5.2));
+    assert!(solution.lower_bound < solution.schedule.makespan);
+}
+
+#[test]
+fn the_best_schedule_so_far_is_kept_when_the_budget_runs_out() {
+    let planned = simulate::simulate(&crowded(), &specs(), 2, DEFAULT_AGING, Strategy::Optimized).unwrap();
+    assert_eq!(planned.makespan, secs(6.5));
+
+    // before the search gets anywhere, the planner's schedule comes back
+    let solution = exact::solve(&crowded(), &specs(), 2, exact::Budget { nodes: Some(0), time: None }).unwrap();
+    assert!(!solution.optimal);
+    assert_eq!(solution.schedule.makespan, planned.makespan);
+    let mut ids: Vec<usize> = solution.schedule.entries.iter().map(|entry| entry.id).collect();
+    ids.sort();
+    assert_eq!(ids, (0..8).collect::<Vec<_>>());
+
+    // a little further, a better one but not the best
+    let solution = exact::solve(&crowded(), &specs(), 2, exact::Budget { nodes: Some(20), time: None }).unwrap();
+    assert!(!solution.optimal);
+    assert_eq!(solution.nodes, 20);
+    assert_eq!(solution.schedule.entries.len(), 8);
+    assert!(solution.schedule.makespan < planned.makespan && solution.schedule.makespan > secs(5.2), "{:?}", solution.schedule.makespan);
+}
+
+#[test]
+fn bad_input_is_an_error() {
+    let jobs = [Job::new(1, "Dave", "mow_the_lawn")];
+    assert!(matches!(exact::solve(&jobs, &specs(), 2, exact::Budget::default()), Err(Error::UnknownTask(task)) if task == "mow_the_lawn"));
+    assert!(matches!(exact::solve(&crowded(), &specs(), 0, exact::Budget::default()), Err(Error::InvalidConcurrency)));
+}
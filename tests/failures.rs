@@ -0,0 +1,269 @@
+// tasks that fail, on tokio's paused clock
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use robot_tech_test::verify::{self, Timeline};
+use robot_tech_test::load::{self, Format};
+use robot_tech_test::{Error, Executor, ExecutorBuilder, Job, OnFailure, OnTimeout, Outcome, RetryPolicy, Strategy, TaskError, clock};
+
+mod common;
+use common::{STRATEGIES, hang, secs};
+
+// a chore taking 0.5 seconds that fails the first `failures` times it runs for each id
+fn flaky(failures: u32) -> impl Fn(usize, String) -> futures::future::BoxFuture<'static, Result<String, TaskError>> + Send + Sync {
+    let runs: Arc<Mutex<HashMap<usize, u32>>> = Arc::default();
+    move |id, _robot| {
+        let runs = runs.clone();
+        Box::pin(async move {
+            clock::sleep(Duration::from_millis(500)).await;
+            let mut runs = runs.lock().unwrap();
+            let run = runs.entry(id).or_default();
+            *run += 1;
+            if *run <= failures { Err("the cat feeder jammed".into()) } else { Ok(String::from("Meow")) }
+        })
+    }
+}
+
+fn household(strategy: Strategy, failures: u32) -> ExecutorBuilder {
+    common::household(&["Dave", "Cris"])
+        .task("feed_the_cat", Duration::from_secs(2), flaky(failures))
+        .concurrency(2)
+        .strategy(strategy)
+}
+
+#[tokio::test(start_paused = true)]
+async fn retries_keep_the_robot_blocked_and_the_ratelimit() {
+    for strategy in STRATEGIES {
+        let policy = RetryPolicy::attempts(3).backoff(Duration::from_millis(100), Duration::from_secs(1)).jitter(0.0);
+        let executor = household(strategy, 1).retry("feed_the_cat", policy).build().unwrap();
+        let report = executor.run([(1, "Dave", "feed_the_cat"), (2, "Dave", "water_the_plants")]).await.unwrap();
+
+        let fed = &report.tasks[&1];
+        assert_eq!(fed.retries.len(), 1, "{strategy:?}");
+        assert_eq!((fed.retries[0].start, fed.retries[0].end), (Duration::ZERO, Duration::from_millis(500)));
+        // the backoff is over at 0.6s but the ratelimiter only lets it in again at 2s
+        assert_eq!((fed.start, fed.end), (Some(secs(2.0)), Some(secs(2.5))), "{strategy:?}");
+        assert_eq!(fed.outcome, Outcome::Finished { output: "Meow".into() });
+        assert_eq!(report.tasks[&2].start, Some(secs(2.5)), "{strategy:?}");
+
+        let violations = verify::verify(&Timeline::from_report(&report), &executor.limits());
+        assert!(violations.is_empty(), "{strategy:?} {violations:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn gives_up_after_the_last_attempt() {
+    for strategy in STRATEGIES {
+        let policy = RetryPolicy::attempts(2).backoff(Duration::from_secs(5), Duration::from_secs(5)).jitter(0.0);
+        let executor = household(strategy, 5).retry("feed_the_cat", policy).build().unwrap();
+        let report = executor.run([(1, "Cris", "feed_the_cat")]).await.unwrap();
+        let fed = &report.tasks[&1];
+        assert_eq!(fed.retries.len(), 1, "{strategy:?}");
+        assert_eq!(fed.start, Some(secs(5.5)), "{strategy:?}");
+        assert_eq!(fed.outcome, Outcome::Failed { error: "the cat feeder jammed".into() });
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_panicking_handler_fails_its_task() {
+    for strategy in STRATEGIES {
+        let executor = household(strategy, 0)
+            .task("clean_the_windows", Duration::from_secs(1), |id, _| async move {
+                clock::sleep(Duration::from_millis(300)).await;
+                assert!(id != 1, "the ladder fell over");
+                Ok::<_, TaskError>(String::from("Squeeesh"))
+            })
+            .retry("clean_the_windows", RetryPolicy::attempts(2).backoff(Duration::from_millis(100), Duration::from_millis(100)).jitter(0.0))
+            .build()
+            .unwrap();
+        let jobs = [(1, "Dave", "clean_the_windows"), (2, "Dave", "feed_the_cat"), (3, "Cris", "clean_the_windows")];
+        let report = executor.run(jobs).await.unwrap();
+
+        // retried like any error, then given up on without taking the session down
+        let cleaned = &report.tasks[&1];
+        assert_eq!(cleaned.retries.len(), 1, "{strategy:?}");
+        assert_eq!(cleaned.retries[0].error, "panicked : the ladder fell over");
+        assert_eq!(cleaned.outcome, Outcome::Failed { error: "panicked : the ladder fell over".into() }, "{strategy:?}");
+        assert_eq!(report.tasks[&2].outcome, Outcome::Finished { output: "Meow".into() }, "{strategy:?}");
+        assert_eq!(report.tasks[&3].outcome, Outcome::Finished { output: "Squeeesh".into() }, "{strategy:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn only_retryable_errors_are_retried() {
+    let policy = RetryPolicy::attempts(5).retry_if(|error| !error.to_string().contains("jammed"));
+    let executor = household(Strategy::Idiomatic, 1).retry("feed_the_cat", policy).build().unwrap();
+    let report = executor.run([(1, "Dave", "feed_the_cat")]).await.unwrap();
+    assert!(report.tasks[&1].retries.is_empty());
+    assert!(matches!(report.tasks[&1].outcome, Outcome::Failed { .. }));
+}
+
+// a chore that never returns, timed out after 2 seconds
+fn stuck(strategy: Strategy, then: OnTimeout) -> Executor {
+    household(strategy, 0)
+        .task("fix_the_boiler", Duration::from_secs(1), hang)
+        .timeout("fix_the_boiler", Duration::from_secs(2), then)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test(start_paused = true)]
+async fn stuck_tasks_time_out_and_free_their_slot() {
+    for strategy in STRATEGIES {
+        let executor = stuck(strategy, OnTimeout::Continue);
+        let jobs = [(1, "Dave", "fix_the_boiler"), (2, "Dave", "water_the_plants"), (3, "Cris", "fix_the_boiler"), (4, "Cris", "feed_the_cat")];
+        let report = executor.run(jobs).await.unwrap();
+
+        for id in [1, 3] {
+            assert_eq!(report.tasks[&id].outcome, Outcome::TimedOut { after: Duration::from_secs(2) }, "{strategy:?}");
+        }
+        // both slots were taken by stuck tasks until they timed out
+        assert_eq!(report.tasks[&3].start, Some(secs(1.0)), "{strategy:?}");
+        assert_eq!(report.tasks[&2].start, Some(secs(2.0)), "{strategy:?}");
+        assert_eq!(report.tasks[&4].start, Some(secs(3.0)), "{strategy:?}");
+        assert_eq!(report.tasks[&2].outcome, Outcome::Finished { output: "Blub".into() });
+
+        let violations = verify::verify(&Timeline::from_report(&report), &executor.limits());
+        assert!(violations.is_empty(), "{strategy:?} {violations:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_timeout_can_skip_the_robot_queue() {
+    for strategy in STRATEGIES {
+        let executor = stuck(strategy, OnTimeout::Skip);
+        let session = executor.start();
+        for job in [(1, "Dave", "fix_the_boiler"), (2, "Dave", "water_the_plants"), (3, "Cris", "feed_the_cat")] {
+            session.submit(job).unwrap();
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        // submitted after the timeout, so it still runs
+        session.submit((4, "Dave", "feed_the_cat")).unwrap();
+        session.close();
+        let report = session.join().await;
+
+        assert_eq!(report.tasks[&2].outcome, Outcome::Skipped { reason: "id 1 timed out before it".into() }, "{strategy:?}");
+        assert_eq!(report.tasks[&2].start, None);
+        assert!(matches!(report.tasks[&3].outcome, Outcome::Finished { .. }), "{strategy:?}");
+        assert!(matches!(report.tasks[&4].outcome, Outcome::Finished { .. }), "{strategy:?}");
+        assert_eq!(report.not_run().collect::<Vec<_>>(), [(2, "id 1 timed out before it")]);
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_timeout_can_halt_the_robot() {
+    for strategy in STRATEGIES {
+        let executor = stuck(strategy, OnTimeout::Halt);
+        let session = executor.start();
+        for job in [(1, "Dave", "fix_the_boiler"), (2, "Dave", "water_the_plants"), (3, "Cris", "feed_the_cat")] {
+            session.submit(job).unwrap();
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        session.submit((4, "Dave", "feed_the_cat")).unwrap();
+        session.submit((5, "Cris", "water_the_plants")).unwrap();
+        session.close();
+        let report = session.join().await;
+
+        for id in [2, 4] {
+            assert_eq!(report.tasks[&id].outcome, Outcome::Skipped { reason: "Dave halted after id 1 timed out".into() }, "{strategy:?}");
+        }
+        assert!(matches!(report.tasks[&5].outcome, Outcome::Finished { .. }), "{strategy:?}");
+    }
+}
+
+// Dave's cat feeding fails, ids 3 and 4 depend on it, the rest doesn't
+fn failing_jobs() -> Vec<Job> {
+    vec![
+        Job::new(1, "Dave", "water_the_plants"),
+        Job::new(2, "Dave", "feed_the_cat"),
+        Job::new(3, "Dave", "water_the_plants").after([2]),
+        Job::new(4, "Dave", "water_the_plants").after([1, 3]),
+        Job::new(5, "Dave", "water_the_plants"),
+        Job::new(6, "Cris", "water_the_plants"),
+    ]
+}
+
+async fn not_run(strategy: Strategy, policy: OnFailure) -> Vec<(usize, String)> {
+    let executor = household(strategy, 1).on_failure("Dave", policy).build().unwrap();
+    let report = executor.run(failing_jobs()).await.unwrap();
+    assert!(matches!(report.tasks[&2].outcome, Outcome::Failed { .. }), "{strategy:?}");
+    assert!(matches!(report.tasks[&6].outcome, Outcome::Finished { .. }), "{strategy:?}");
+    let violations = verify::verify(&Timeline::from_report(&report), &executor.limits());
+    assert!(violations.is_empty(), "{strategy:?} {violations:?}");
+    report.not_run().map(|(id, reason)| (id, reason.to_owned())).collect()
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_failure_is_handled_per_robot() {
+    for strategy in STRATEGIES {
+        assert_eq!(not_run(strategy, OnFailure::Continue).await, []);
+
+        let skipped = not_run(strategy, OnFailure::SkipDependents).await;
+        assert_eq!(skipped, [
+            (3, String::from("depends on id 2 which didn't finish")),
+            (4, String::from("depends on id 3 which didn't finish")),
+        ], "{strategy:?}");
+
+        let halted = not_run(strategy, OnFailure::Halt).await;
+        let reason = String::from("Dave halted after id 2 failed");
+        assert_eq!(halted, [(3, reason.clone()), (4, reason.clone()), (5, reason)], "{strategy:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_failure_skips_the_dependents_on_other_robots() {
+    for strategy in STRATEGIES {
+        let executor = household(strategy, 1).on_failure("Cris", OnFailure::SkipDependents).build().unwrap();
+        let jobs = [
+            Job::new(1, "Dave", "water_the_plants"),
+            Job::new(2, "Dave", "feed_the_cat"),
+            Job::new(3, "Cris", "feed_the_cat").after([2]),
+            Job::new(4, "Cris", "water_the_plants").after([1]),
+        ];
+        let report = executor.run(jobs).await.unwrap();
+        assert_eq!(report.not_run().collect::<Vec<_>>(), [(3, "depends on id 2 which didn't finish")], "{strategy:?}");
+        // waited for Cris's skipped job, then for the ratelimiter
+        assert_eq!(report.tasks[&4].start, Some(secs(3.0)), "{strategy:?}");
+        assert!(matches!(report.tasks[&4].outcome, Outcome::Finished { .. }), "{strategy:?}");
+    }
+}
+
+#[test]
+fn dependencies_must_not_loop() {
+    let executor = household(Strategy::Idiomatic, 0).build().unwrap();
+    // the robot already runs 1 before 2
+    let backwards = [Job::new(1, "Dave", "feed_the_cat").after([2]), Job::new(2, "Dave", "feed_the_cat")];
+    assert!(matches!(executor.validate(&backwards), Err(Error::Dependency { id: 2, .. })));
+    let other_robot = [Job::new(1, "Cris", "feed_the_cat"), Job::new(2, "Dave", "feed_the_cat").after([1])];
+    assert!(executor.validate(&other_robot).is_ok());
+    let missing = [Job::new(1, "Dave", "feed_the_cat").after([7])];
+    assert!(matches!(executor.validate(&missing), Err(Error::Dependency { id: 1, .. })));
+
+    let jobs = load::parse("id,robot,task,after\n1,Dave,feed_the_cat\n2,Dave,feed_the_cat,1\n", Format::Csv).unwrap();
+    assert_eq!(jobs[1], Job::new(2, "Dave", "feed_the_cat").after([1]));
+    assert!(executor.validate(&jobs).is_ok());
+}
+
+#[test]
+fn a_zero_timeout_is_rejected() {
+    let built = household(Strategy::Idiomatic, 0).timeout("feed_the_cat", Duration::ZERO, OnTimeout::Continue).build();
+    assert!(built.is_err());
+    let built = household(Strategy::Idiomatic, 0).timeout("mow_the_lawn", Duration::from_secs(1), OnTimeout::Continue).build();
+    assert!(built.is_err());
+}
+
+#[test]
+fn backoff_grows_up_to_the_max() {
+    let policy = RetryPolicy::attempts(10).backoff(Duration::from_secs(1), Duration::from_secs(5)).jitter(0.0);
+    let error: TaskError = "jammed".into();
+    let delays: Vec<Option<Duration>> = (1..=10).map(|attempt| policy.delay(1, attempt, &error)).collect();
+    assert_eq!(&delays[..4], [Some(secs(1.0)), Some(secs(2.0)), Some(secs(4.0)), Some(secs(5.0))]);
+    assert_eq!(delays[9], None);
+
+    let jittered = policy.jitter(0.5);
+    for attempt in 1..10 {
+        let delay = jittered.delay(7, attempt, &error).unwrap();
+        assert!(delay <= delays[attempt as usize - 1].unwrap() && delay >= delays[attempt as usize - 1].unwrap() / 2);
+        assert_eq!(Some(delay), jittered.delay(7, attempt, &error));
+    }
+}
@@ -0,0 +1,103 @@
+// job files in either format, with errors pointing at the line they come from
+use std::time::Duration;
+use robot_tech_test::load::{self, Format};
+use robot_tech_test::{Error, Executor, Job, TaskError};
+
+fn parse_error(input: &str) -> (usize, String) {
+    match load::parse(input, Format::detect(input)) {
+        Err(Error::Parse { line, message }) => (line, message),
+        other => panic!("expected a parse error, got {other:?}"),
+    }
+}
+
+#[test]
+fn both_formats_read_the_same_jobs() {
+    let json = r#"
+        {"id":1,"robot":"Dave","task":"clean_the_windows"}
+        {"id":2,"robot":"Cris","task":"feed_the_cat","after":[1]}
+    "#;
+    let csv = "1,Dave,clean_the_windows\n2,Cris,feed_the_cat,1\n";
+    assert_eq!(Format::detect(json), Format::JsonLines);
+    assert_eq!(Format::detect(csv), Format::Csv);
+    let jobs = vec![Job::new(1, "Dave", "clean_the_windows"), Job::new(2, "Cris", "feed_the_cat").after([1])];
+    assert_eq!(load::parse(json, Format::JsonLines).unwrap(), jobs);
+    assert_eq!(load::parse(csv, Format::Csv).unwrap(), jobs);
+}
+
+#[test]
+fn csv_headers_are_skipped() {
+    let jobs = load::parse("id,robot,task\n1,Dave,clean_the_windows\n", Format::Csv).unwrap();
+    assert_eq!(jobs, [Job::new(1, "Dave", "clean_the_windows")]);
+    let jobs = load::parse("id, robot, task, after\n1,Dave,clean_the_windows\n2,Dave,feed_the_cat,1\n", Format::Csv).unwrap();
+    assert_eq!(jobs, [Job::new(1, "Dave", "clean_the_windows"), Job::new(2, "Dave", "feed_the_cat").after([1])]);
+}
+
+#[test]
+fn errors_count_the_blank_lines_too() {
+    assert_eq!(parse_error("1,Dave,clean_the_windows\n\n\nx,Dave,feed_the_cat\n").0, 4);
+    let json = "{\"id\":1,\"robot\":\"Dave\",\"task\":\"feed_the_cat\"}\n\n{\"id\":2,\"robot\":\"Dave\"}\n";
+    let (line, message) = parse_error(json);
+    assert_eq!(line, 3);
+    assert!(message.contains("task"), "{message}");
+}
+
+#[test]
+fn csv_lines_need_3_to_6_fields() {
+    let (line, message) = parse_error("1,Dave\n");
+    assert_eq!(line, 1);
+    assert!(message.contains("got 2"), "{message}");
+    let (line, message) = parse_error("1,Dave,feed_the_cat\n2,Dave,feed_the_cat,1,5,12.0,oops\n");
+    assert_eq!(line, 2);
+    assert!(message.contains("got 7"), "{message}");
+    assert!(parse_error("1,Dave,feed_the_cat,,high\n").1.contains("invalid priority \"high\""));
+    assert!(parse_error("1,Dave,feed_the_cat,,,soon\n").1.contains("invalid deadline \"soon\""));
+    assert!(parse_error("1,Dave,feed_the_cat,,,-1\n").1.contains("invalid deadline"));
+    assert!(parse_error("1,Dave,feed_the_cat,one\n").1.contains("invalid id \"one\" in after"));
+}
+
+#[test]
+fn csv_has_columns_for_the_priority_and_the_deadline() {
+    let csv = "id,robot,task,after,priority,deadline\n1,Dave,feed_the_cat,,5\n2,Cris,feed_the_cat,1,,12.5\n3,Cris,feed_the_cat,,-1,3\n";
+    let json = r#"
+        {"id":1,"robot":"Dave","task":"feed_the_cat","priority":5}
+        {"id":2,"robot":"Cris","task":"feed_the_cat","after":[1],"deadline":12.5}
+        {"id":3,"robot":"Cris","task":"feed_the_cat","priority":-1,"deadline":3}
+    "#;
+    let jobs = vec![
+        Job::new(1, "Dave", "feed_the_cat").priority(5),
+        Job::new(2, "Cris", "feed_the_cat").after([1]).deadline(Duration::from_millis(12_500)),
+        Job::new(3, "Cris", "feed_the_cat").priority(-1).deadline(Duration::from_secs(3)),
+    ];
+    assert_eq!(load::parse(csv, Format::Csv).unwrap(), jobs);
+    assert_eq!(load::parse(json, Format::JsonLines).unwrap(), jobs);
+}
+
+#[test]
+fn misspelled_json_fields_are_rejected() {
+    let (line, message) = parse_error("{\"id\":1,\"robot\":\"Dave\",\"task\":\"feed_the_cat\",\"priorty\":5}\n");
+    assert_eq!(line, 1);
+    assert!(message.contains("unknown field `priorty`"), "{message}");
+}
+
+#[test]
+fn duplicate_ids_point_at_the_first_use() {
+    let (line, message) = parse_error("1,Dave,feed_the_cat\n\n2,Cris,feed_the_cat\n1,Cris,clean_the_windows\n1,Dave,clean_the_windows\n");
+    assert_eq!(line, 4);
+    assert_eq!(message, "duplicate id 1 (first used on line 1)");
+}
+
+#[test]
+fn jobs_naming_unknown_robots_or_tasks_are_rejected() {
+    let executor = Executor::builder()
+        .robots(["Dave", "Cris"])
+        .task("feed_the_cat", Duration::from_secs(2), |_, _| async { Ok::<_, TaskError>(String::from("Meow")) })
+        .quiet()
+        .build()
+        .unwrap();
+    let jobs = load::parse("1,Dave,feed_the_cat\n2,Phil,feed_the_cat\n", Format::Csv).unwrap();
+    assert_eq!(executor.validate(&jobs), Err(Error::UnknownRobot("Phil".into())));
+    let jobs = load::parse("1,Dave,feed_the_cat\n2,Cris,mow_the_lawn\n", Format::Csv).unwrap();
+    assert_eq!(executor.validate(&jobs), Err(Error::UnknownTask("mow_the_lawn".into())));
+    let jobs = load::parse("1,Dave,feed_the_cat\n2,Cris,feed_the_cat,1\n", Format::Csv).unwrap();
+    assert_eq!(executor.validate(&jobs), Ok(()));
+}
@@ -0,0 +1,136 @@
+// robots joining and leaving a running session, on tokio's paused clock
+use std::time::Duration;
+use robot_tech_test::config::Config;
+use robot_tech_test::{Error, Executor, ExecutorBuilder, Job, Outcome, Retire, Strategy};
+
+mod common;
+use common::STRATEGIES;
+
+fn household(strategy: Strategy) -> Executor {
+    chores().strategy(strategy).build().unwrap()
+}
+
+fn chores() -> ExecutorBuilder {
+    common::household(&["Dave", "Cris"]).concurrency(2)
+}
+
+fn ids(jobs: &[Job]) -> Vec<usize> {
+    jobs.iter().map(|job| job.id).collect()
+}
+
+#[tokio::test(start_paused = true)]
+async fn robots_can_join_a_running_session() {
+    for strategy in STRATEGIES {
+        let session = household(strategy).start();
+        assert_eq!(session.submit((1, "Phil", "feed_the_cat")), Err(Error::UnknownRobot("Phil".into())));
+        session.add_robot("Phil").unwrap();
+        assert_eq!(session.add_robot("Phil"), Err(Error::DuplicateRobot("Phil".into())));
+        session.submit((1, "Phil", "feed_the_cat")).unwrap();
+        session.submit((2, "Dave", "water_the_plants")).unwrap();
+        session.close();
+        let report = session.join().await;
+
+        assert_eq!(report.tasks[&1].robot, "Phil");
+        assert_eq!(report.tasks[&1].outcome, Outcome::Finished { output: "Meow".into() }, "{strategy:?}");
+        assert_eq!(report.tasks[&2].outcome, Outcome::Finished { output: "Blub".into() }, "{strategy:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_retired_robot_can_drain_its_queue() {
+    for strategy in STRATEGIES {
+        let start = tokio::time::Instant::now();
+        let session = household(strategy).start();
+        for job in [(1, "Dave", "feed_the_cat"), (2, "Dave", "feed_the_cat"), (3, "Cris", "water_the_plants")] {
+            session.submit(job).unwrap();
+        }
+        let handed_back = session.retire_robot("Dave", Retire::Drain).await.unwrap();
+        assert_eq!(handed_back, []);
+        // its last task ended at 2.5s
+        assert_eq!(start.elapsed(), Duration::from_millis(2500), "{strategy:?}");
+        assert_eq!(session.submit((4, "Dave", "feed_the_cat")), Err(Error::UnknownRobot("Dave".into())));
+
+        // and come back
+        session.add_robot("Dave").unwrap();
+        session.submit((4, "Dave", "feed_the_cat")).unwrap();
+        session.close();
+        let report = session.join().await;
+        assert!(report.tasks.values().all(|task| matches!(task.outcome, Outcome::Finished { .. })), "{strategy:?}");
+        assert_eq!(report.tasks.len(), 4);
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_robot_retired_now_hands_back_its_unstarted_jobs() {
+    for strategy in STRATEGIES {
+        let start = tokio::time::Instant::now();
+        let session = household(strategy).start();
+        let jobs = [(1, "Dave", "water_the_plants"), (2, "Dave", "feed_the_cat"), (3, "Dave", "feed_the_cat"), (4, "Cris", "feed_the_cat")];
+        for job in jobs {
+            session.submit(job).unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let handed_back = session.retire_robot("Dave", Retire::Now).await.unwrap();
+        assert_eq!(ids(&handed_back), [2, 3], "{strategy:?}");
+        // once the task it was running ended
+        assert_eq!(start.elapsed(), Duration::from_millis(700), "{strategy:?}");
+
+        for job in handed_back {
+            session.submit(Job { robot: "Cris".into(), ..job }).unwrap();
+        }
+        session.close();
+        let report = session.join().await;
+        assert_eq!(report.tasks[&1].robot, "Dave");
+        for id in [2, 3, 4] {
+            assert_eq!(report.tasks[&id].robot, "Cris", "{strategy:?}");
+            assert!(matches!(report.tasks[&id].outcome, Outcome::Finished { .. }), "{strategy:?}");
+        }
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn only_working_robots_can_be_retired() {
+    let session = household(Strategy::Idiomatic).start();
+    assert_eq!(session.retire_robot("Phil", Retire::Now).await, Err(Error::UnknownRobot("Phil".into())));
+    session.close();
+    assert_eq!(session.retire_robot("Dave", Retire::Drain).await, Err(Error::Closed));
+    session.join().await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn robots_only_get_the_tasks_they_can_do() {
+    let incapable = |robot: &str, task: &str| Err(Error::Incapable { robot: robot.into(), task: task.into() });
+    for strategy in STRATEGIES {
+        let executor = chores().robot_capable_of("Nick", ["water_the_plants"]).strategy(strategy).build().unwrap();
+        assert_eq!(executor.validate(&[Job::new(1, "Nick", "feed_the_cat")]), incapable("Nick", "feed_the_cat"));
+
+        let session = executor.start();
+        assert_eq!(session.submit((1, "Nick", "feed_the_cat")), incapable("Nick", "feed_the_cat"));
+        session.submit((1, "Nick", "water_the_plants")).unwrap();
+        assert_eq!(session.add_robot_capable_of("Phil", ["mow_the_lawn"]), Err(Error::UnknownTask("mow_the_lawn".into())));
+        session.add_robot_capable_of("Phil", ["feed_the_cat"]).unwrap();
+        assert_eq!(session.submit((2, "Phil", "water_the_plants")), incapable("Phil", "water_the_plants"));
+        session.submit((2, "Phil", "feed_the_cat")).unwrap();
+        session.close();
+        let report = session.join().await;
+        assert!(report.tasks.values().all(|task| matches!(task.outcome, Outcome::Finished { .. })), "{strategy:?}");
+        assert_eq!(report.tasks.len(), 2);
+    }
+}
+
+#[test]
+fn capabilities_name_known_task_types() {
+    assert!(chores().robot_capable_of("Nick", ["mow_the_lawn"]).build().is_err());
+
+    let config = r#"
+        concurrency = 1
+        robots = ["Nick"]
+        capabilities = { Nick = ["clean_the_windows"] }
+        [tasks.clean_the_windows]
+        duration = 0.3
+        interval = 5.0
+    "#;
+    let executor = Config::from_toml(config).unwrap().builder().quiet().build().unwrap();
+    assert!(executor.validate(&[Job::new(1, "Nick", "clean_the_windows")]).is_ok());
+    assert!(Config::from_toml(&config.replace("[\"clean_the_windows\"]", "[\"feed_the_cat\"]")).is_err());
+}
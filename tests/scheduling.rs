@@ -0,0 +1,269 @@
+// which robot goes first when several compete for a token or a slot, on tokio's paused clock
+use std::collections::HashMap;
+use std::time::Duration;
+use robot_tech_test::schedule::TaskSpec;
+use robot_tech_test::{DEFAULT_AGING, Error, Executor, Job, Rate, Report, Strategy, exact, simulate};
+
+mod common;
+use common::{STRATEGIES, chore, secs};
+
+fn household(strategy: Strategy, aging: Duration) -> Executor {
+    common::household(&["Andi", "Dave", "Cris"]).concurrency(3).aging(aging).strategy(strategy).build().unwrap()
+}
+
+// Andi takes the first token, the other jobs are submitted right after so they all compete for
+// the next ones
+async fn compete(executor: &Executor, jobs: impl IntoIterator<Item = Job>) -> Report {
+    let session = executor.start();
+    session.submit((0, "Andi", "feed_the_cat")).unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    for job in jobs {
+        session.submit(job).unwrap();
+    }
+    session.close();
+    session.join().await
+}
+
+fn start(report: &Report, id: usize) -> Duration {
+    report.tasks[&id].start.expect("task started")
+}
+
+#[tokio::test(start_paused = true)]
+async fn urgent_jobs_get_the_next_token() {
+    for strategy in STRATEGIES {
+        let executor = household(strategy, Duration::from_secs(60));
+        let jobs = [Job::new(1, "Dave", "feed_the_cat"), Job::new(2, "Dave", "feed_the_cat"), Job::new(3, "Cris", "feed_the_cat").priority(5)];
+        let report = compete(&executor, jobs).await;
+        assert_eq!(start(&report, 3), Duration::from_secs(2), "{strategy:?}");
+        assert_eq!(start(&report, 1), Duration::from_secs(4), "{strategy:?}");
+        assert_eq!(start(&report, 2), Duration::from_secs(6), "{strategy:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn aging_keeps_low_priority_robots_from_starving() {
+    let jobs = || std::iter::once(Job::new(1, "Dave", "feed_the_cat"))
+        .chain((2..8).map(|id| Job::new(id, "Cris", "feed_the_cat").priority(1)));
+    for strategy in STRATEGIES {
+        // without aging Dave goes last
+        let report = compete(&household(strategy, Duration::from_secs(3600)), jobs()).await;
+        assert_eq!(start(&report, 1), Duration::from_secs(14), "{strategy:?}");
+
+        // after 3 seconds Dave's job is as urgent as Cris's, after 6 seconds it is more urgent
+        let report = compete(&household(strategy, Duration::from_secs(3)), jobs()).await;
+        assert!(start(&report, 1) <= Duration::from_secs(8), "{strategy:?} {:?}", start(&report, 1));
+        assert!(start(&report, 7) > start(&report, 1), "{strategy:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn the_earliest_deadline_goes_first() {
+    for strategy in STRATEGIES {
+        let executor = household(strategy, Duration::from_secs(60));
+        let jobs = [Job::new(1, "Dave", "feed_the_cat").deadline(secs(4.0)), Job::new(2, "Cris", "feed_the_cat").deadline(secs(3.0))];
+        let report = compete(&executor, jobs).await;
+        assert_eq!(start(&report, 2), secs(2.0), "{strategy:?}");
+        assert_eq!(start(&report, 1), secs(4.0), "{strategy:?}");
+        assert_eq!(report.missed_deadlines().collect::<Vec<_>>(), [(1, secs(4.0))], "{strategy:?}");
+
+        // a higher priority still goes before an earlier deadline
+        let jobs = [Job::new(1, "Dave", "feed_the_cat").priority(1), Job::new(2, "Cris", "feed_the_cat").deadline(secs(3.0))];
+        let report = compete(&executor, jobs).await;
+        assert!(start(&report, 1) < start(&report, 2), "{strategy:?}");
+    }
+}
+
+// a session's timeline starts with it, however long ago the executor was built
+#[tokio::test(start_paused = true)]
+async fn deadlines_count_from_the_start_of_the_session() {
+    for strategy in STRATEGIES {
+        let executor = household(strategy, DEFAULT_AGING);
+        executor.run([Job::new(1, "Dave", "feed_the_cat")]).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(60)).await;
+
+        let report = executor.run([Job::new(1, "Dave", "feed_the_cat").deadline(secs(1.0))]).await.unwrap();
+        assert_eq!((report.tasks[&1].start, report.tasks[&1].end), (Some(secs(0.0)), Some(secs(0.5))), "{strategy:?}");
+        assert_eq!(report.missed_deadlines().count(), 0, "{strategy:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn the_simulation_predicts_missed_deadlines() {
+    let jobs = [
+        Job::new(1, "Andi", "feed_the_cat"),
+        Job::new(2, "Dave", "feed_the_cat").deadline(secs(1.0)),
+        Job::new(3, "Cris", "feed_the_cat").deadline(secs(3.0)),
+        Job::new(4, "Cris", "feed_the_cat").deadline(secs(5.0)),
+        Job::new(5, "Dave", "feed_the_cat").deadline(secs(6.0)),
+    ];
+    let specs = HashMap::from([("feed_the_cat".to_owned(), TaskSpec { duration: secs(0.5), interval: secs(2.0), burst: 1 })]);
+    for strategy in STRATEGIES {
+        let schedule = simulate::simulate(&jobs, &specs, 3, DEFAULT_AGING, strategy).unwrap();
+        assert_eq!(schedule.missed_deadlines(&jobs), [(5, secs(6.0), Some(secs(6.5)))], "{strategy:?}");
+    }
+    let report = household(Strategy::Optimized, DEFAULT_AGING).run(jobs).await.unwrap();
+    assert_eq!(report.missed_deadlines().collect::<Vec<_>>(), [(5, secs(6.0))]);
+}
+
+#[test]
+fn the_simulation_rejects_what_the_executor_would() {
+    let jobs = [Job::new(1, "Dave", "feed_the_cat"), Job::new(2, "Cris", "mow_the_lawn")];
+    let specs = HashMap::from([("feed_the_cat".to_owned(), TaskSpec { duration: secs(0.5), interval: secs(2.0), burst: 1 })]);
+    for strategy in STRATEGIES {
+        assert_eq!(simulate::simulate(&jobs, &specs, 3, DEFAULT_AGING, strategy).err(), Some(Error::UnknownTask("mow_the_lawn".into())));
+        assert_eq!(simulate::simulate(&jobs[..1], &specs, 0, DEFAULT_AGING, strategy).err(), Some(Error::InvalidConcurrency));
+        assert_eq!(simulate::simulate(&jobs[..1], &specs, 3, Duration::ZERO, strategy).err(), Some(Error::InvalidAging));
+    }
+}
+
+// jobs that wait on each other would never start, the simulation and the exact solver reject them
+// like `validate` does
+#[test]
+fn plans_reject_jobs_waiting_on_each_other() {
+    let specs = HashMap::from([("feed_the_cat".to_owned(), TaskSpec { duration: secs(0.5), interval: secs(2.0), burst: 1 })]);
+    let dependency = |jobs: &[Job], id: usize| {
+        for strategy in STRATEGIES {
+            let simulated = simulate::simulate(jobs, &specs, 3, DEFAULT_AGING, strategy);
+            assert!(matches!(simulated, Err(Error::Dependency { id: i, .. }) if i == id), "{strategy:?} {:?}", simulated.err());
+        }
+        let solved = exact::solve(jobs, &specs, 3, exact::Budget::default());
+        assert!(matches!(solved, Err(Error::Dependency { id: i, .. }) if i == id), "{:?}", solved.err());
+    };
+    // a cycle across robots
+    dependency(&[Job::new(1, "Dave", "feed_the_cat").after([2]), Job::new(2, "Cris", "feed_the_cat").after([1])], 2);
+    // Dave can't run 1 before 2, which comes after it in his own queue
+    dependency(&[Job::new(1, "Dave", "feed_the_cat").after([2]), Job::new(2, "Dave", "feed_the_cat")], 2);
+    // nor wait for a job that isn't there
+    dependency(&[Job::new(1, "Dave", "feed_the_cat").after([7])], 1);
+
+    let twice = [Job::new(1, "Dave", "feed_the_cat"), Job::new(1, "Cris", "feed_the_cat")];
+    for strategy in STRATEGIES {
+        assert_eq!(simulate::simulate(&twice, &specs, 3, DEFAULT_AGING, strategy).err(), Some(Error::DuplicateId(1)));
+    }
+    assert!(matches!(exact::solve(&twice, &specs, 3, exact::Budget::default()), Err(Error::DuplicateId(1))));
+}
+
+#[tokio::test(start_paused = true)]
+async fn robots_wait_for_the_jobs_of_others() {
+    // Cris's job has to wait for Dave's second one, submitted after it
+    let jobs = || [Job::new(1, "Dave", "feed_the_cat"), Job::new(2, "Cris", "feed_the_cat").after([3]), Job::new(3, "Dave", "feed_the_cat")];
+    for strategy in STRATEGIES {
+        let report = compete(&household(strategy, DEFAULT_AGING), jobs()).await;
+        assert_eq!([1, 3, 2].map(|id| start(&report, id)), [secs(2.0), secs(4.0), secs(6.0)], "{strategy:?}");
+    }
+
+    let planned: Vec<Job> = std::iter::once(Job::new(0, "Andi", "feed_the_cat")).chain(jobs()).collect();
+    let specs = HashMap::from([("feed_the_cat".to_owned(), TaskSpec { duration: secs(0.5), interval: secs(2.0), burst: 1 })]);
+    for strategy in STRATEGIES {
+        let schedule = simulate::simulate(&planned, &specs, 3, DEFAULT_AGING, strategy).unwrap();
+        let order: Vec<usize> = schedule.entries.iter().map(|entry| entry.id).collect();
+        assert_eq!(order[2..], [3, 2], "{strategy:?}");
+    }
+    let solution = exact::solve(&planned, &specs, 3, exact::Budget::default()).unwrap();
+    let cris = solution.schedule.entries.iter().find(|entry| entry.id == 2).unwrap();
+    let dave = solution.schedule.entries.iter().find(|entry| entry.id == 3).unwrap();
+    assert!(cris.start >= dave.end);
+}
+
+#[tokio::test(start_paused = true)]
+async fn dependencies_that_loop_are_rejected() {
+    let executor = household(Strategy::Idiomatic, DEFAULT_AGING);
+    let jobs = [Job::new(1, "Dave", "feed_the_cat"), Job::new(2, "Cris", "feed_the_cat").after([3]), Job::new(3, "Dave", "feed_the_cat").after([2])];
+    assert!(matches!(executor.validate(&jobs), Err(Error::Dependency { id: 3, .. })));
+
+    let session = executor.start();
+    let [first, second, third] = jobs;
+    session.submit(first).unwrap();
+    session.submit(second).unwrap();
+    assert!(matches!(session.submit(third), Err(Error::Dependency { id: 3, .. })));
+    // Cris's job still waits for an id 3, which can come from another robot
+    session.submit(Job::new(3, "Andi", "feed_the_cat")).unwrap();
+    session.close();
+    let report = session.join().await;
+    assert!(start(&report, 2) > start(&report, 3));
+}
+
+// the check for loops only looks back from a job that was named before it was submitted, a
+// long running session doesn't slow down with every job it takes
+#[tokio::test(start_paused = true)]
+async fn long_queues_are_checked_quickly() {
+    let executor = household(Strategy::Idiomatic, DEFAULT_AGING);
+    let jobs: Vec<Job> = (0..50_000).map(|id| Job::new(id, "Dave", "feed_the_cat").after(id.checked_sub(2))).collect();
+    let started = std::time::Instant::now();
+    executor.validate(&jobs).unwrap();
+    let session = executor.start();
+    for job in jobs {
+        session.submit(job).unwrap();
+    }
+    assert!(started.elapsed() < Duration::from_secs(5), "{:?}", started.elapsed());
+    session.abort();
+    session.join().await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn sessions_of_one_executor_share_the_slots() {
+    for strategy in STRATEGIES {
+        let executor = Executor::builder()
+            .robots(["Dave", "Cris"])
+            .task("feed_the_cat", Duration::from_millis(1), chore(1000, "Meow"))
+            .concurrency(2)
+            .strategy(strategy)
+            .quiet()
+            .build()
+            .unwrap();
+        let sessions = [executor.start(), executor.start()];
+        for session in &sessions {
+            for job in [(1, "Dave", "feed_the_cat"), (2, "Cris", "feed_the_cat"), (3, "Dave", "feed_the_cat"), (4, "Cris", "feed_the_cat")] {
+                session.submit(job).unwrap();
+            }
+            session.close();
+        }
+        let joined = futures::future::join_all(sessions.map(|session| session.join()));
+        let reports = tokio::time::timeout(Duration::from_secs(600), joined).await.expect("both sessions end");
+
+        // 8 one second tasks on 2 slots, the ratelimit staggers them by a millisecond
+        let last = reports.iter().flat_map(|report| report.tasks.values()).map(|task| task.end.expect("task ended")).max().unwrap();
+        assert!(last >= secs(4.0) && last < secs(4.1), "{strategy:?} {last:?}");
+    }
+}
+
+// a quota of 3 per 1.5s lets 3 starts through at once, whatever plans or runs them
+#[tokio::test(start_paused = true)]
+async fn bursts_are_planned_for() {
+    let jobs = [Job::new(1, "Andi", "water_the_plants"), Job::new(2, "Dave", "water_the_plants"), Job::new(3, "Cris", "water_the_plants")];
+    for strategy in STRATEGIES {
+        let executor = Executor::builder()
+            .robots(["Andi", "Dave", "Cris"])
+            .task("water_the_plants", Rate::every(Duration::from_millis(500)).burst(3), chore(50, "Blub"))
+            .concurrency(3)
+            .strategy(strategy)
+            .quiet()
+            .build()
+            .unwrap();
+        let report = executor.run(jobs.clone()).await.unwrap();
+        assert!(report.tasks.values().all(|task| task.start == Some(Duration::ZERO)), "{strategy:?}");
+    }
+
+    let specs = HashMap::from([("water_the_plants".to_owned(), TaskSpec { duration: secs(0.05), interval: secs(0.5), burst: 3 })]);
+    for strategy in STRATEGIES {
+        let schedule = simulate::simulate(&jobs, &specs, 3, DEFAULT_AGING, strategy).unwrap();
+        assert_eq!(schedule.makespan, secs(0.05), "{strategy:?}");
+    }
+    let solution = exact::solve(&jobs, &specs, 3, exact::Budget::default()).unwrap();
+    assert_eq!((solution.schedule.makespan, solution.lower_bound, solution.optimal), (secs(0.05), secs(0.05), true));
+
+    // a fourth start waits for the bucket to refill by one
+    let jobs: Vec<Job> = jobs.into_iter().chain([Job::new(4, "Andi", "water_the_plants")]).collect();
+    for strategy in STRATEGIES {
+        let schedule = simulate::simulate(&jobs, &specs, 3, DEFAULT_AGING, strategy).unwrap();
+        assert_eq!(schedule.makespan, secs(0.55), "{strategy:?}");
+    }
+    let solution = exact::solve(&jobs, &specs, 3, exact::Budget::default()).unwrap();
+    assert_eq!((solution.schedule.makespan, solution.optimal), (secs(0.55), true));
+}
+
+#[test]
+fn aging_must_be_positive() {
+    let built = Executor::builder().robot("Dave").aging(Duration::ZERO).build();
+    assert!(built.is_err());
+}
@@ -0,0 +1,59 @@
+// draining and aborting sessions, on tokio's paused clock
+use std::time::Duration;
+use robot_tech_test::{ExecutorBuilder, Outcome, Strategy};
+
+mod common;
+use common::{STRATEGIES, hang};
+
+fn household(strategy: Strategy) -> ExecutorBuilder {
+    common::household(&["Dave", "Cris"]).task("fix_the_boiler", Duration::from_secs(1), hang).strategy(strategy)
+}
+
+#[tokio::test(start_paused = true)]
+async fn draining_lets_the_running_tasks_finish() {
+    for strategy in STRATEGIES {
+        let executor = household(strategy).concurrency(2).build().unwrap();
+        let session = executor.start();
+        let jobs = [(1, "Dave", "water_the_plants"), (2, "Cris", "feed_the_cat"), (3, "Dave", "feed_the_cat"), (4, "Cris", "water_the_plants")];
+        for job in jobs {
+            session.submit(job).unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        session.drain();
+        assert!(session.submit((5, "Dave", "feed_the_cat")).is_err());
+        let report = session.join().await;
+
+        assert_eq!(report.tasks[&1].outcome, Outcome::Finished { output: "Blub".into() }, "{strategy:?}");
+        assert_eq!(report.tasks[&2].outcome, Outcome::Finished { output: "Meow".into() }, "{strategy:?}");
+        assert_eq!(report.not_run().collect::<Vec<_>>(), [(3, "shut down before it started"), (4, "shut down before it started")]);
+        assert_eq!(report.in_flight().count(), 0);
+        assert_eq!(report.makespan(), Duration::from_millis(700), "{strategy:?}");
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn aborting_drops_the_running_tasks() {
+    for strategy in STRATEGIES {
+        let executor = household(strategy).concurrency(1).build().unwrap();
+        let session = executor.start();
+        session.submit((1, "Dave", "fix_the_boiler")).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        session.submit((2, "Cris", "feed_the_cat")).unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        session.drain();
+
+        let sender = session.sender();
+        let mut join = std::pin::pin!(session.join());
+        assert!(tokio::time::timeout(Duration::from_secs(60), &mut join).await.is_err(), "{strategy:?}");
+        sender.abort();
+        let report = join.await;
+
+        assert_eq!(report.in_flight().collect::<Vec<_>>(), [1], "{strategy:?}");
+        assert_eq!(report.tasks[&1].end, None);
+        assert_eq!(report.not_run().collect::<Vec<_>>(), [(2, "shut down before it started")], "{strategy:?}");
+
+        // the only slot was given back
+        let report = executor.run([(3, "Cris", "feed_the_cat")]).await.unwrap();
+        assert!(matches!(report.tasks[&3].outcome, Outcome::Finished { .. }), "{strategy:?}");
+    }
+}
@@ -0,0 +1,169 @@
+// the verifier finds every kind of violation, with the ids and timestamps involved
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use robot_tech_test::schedule::Entry;
+use robot_tech_test::verify::{self, DEFAULT_TOLERANCE, Limits, Timeline, Violation};
+use robot_tech_test::{Executor, ExecutorEvent, Rate, Strategy};
+
+mod common;
+use common::{STRATEGIES, chore, secs};
+
+fn entry(id: usize, robot: &str, task: &str, start: f64, end: f64) -> Entry {
+    Entry { id, robot: robot.into(), task: task.into(), start: secs(start), end: secs(end) }
+}
+
+// feeding the cat once every 2 seconds, watering twice in a row then once a second, cleaning at will
+fn limits() -> Limits {
+    Limits {
+        concurrency: 2,
+        rates: HashMap::from([
+            ("feed_the_cat".into(), Rate::every(secs(2.0))),
+            ("water_the_plants".into(), Rate::every(secs(1.0)).burst(2)),
+            ("clean_the_windows".into(), Rate::every(Duration::from_nanos(1))),
+        ]),
+        tolerance: DEFAULT_TOLERANCE,
+    }
+}
+
+// queued in the order of their ids
+fn timeline(entries: Vec<Entry>) -> Timeline {
+    let mut order: Vec<usize> = entries.iter().map(|e| e.id).collect();
+    order.sort();
+    Timeline { entries, order }
+}
+
+#[test]
+fn a_run_within_the_limits_passes() {
+    let run = timeline(vec![
+        entry(1, "Dave", "feed_the_cat", 0.0, 0.5),
+        entry(2, "Cris", "water_the_plants", 0.0, 0.7),
+        entry(3, "Dave", "water_the_plants", 0.5, 1.2),
+        entry(4, "Cris", "feed_the_cat", 2.0, 2.5),
+    ]);
+    assert_eq!(verify::verify(&run, &limits()), []);
+}
+
+#[test]
+fn too_many_tasks_at_once() {
+    let run = timeline(vec![
+        entry(1, "Dave", "clean_the_windows", 0.0, 1.0),
+        entry(2, "Cris", "clean_the_windows", 0.0, 1.0),
+        entry(3, "Andi", "clean_the_windows", 0.5, 1.5),
+        // starts as 1 and 2 end
+        entry(4, "Nick", "clean_the_windows", 1.0, 2.0),
+    ]);
+    assert_eq!(verify::verify(&run, &limits()), [Violation::Concurrency { at: secs(0.5), running: vec![1, 2, 3], limit: 2 }]);
+}
+
+#[test]
+fn a_robot_runs_one_task_at_a_time() {
+    let run = timeline(vec![entry(1, "Dave", "clean_the_windows", 0.0, 1.0), entry(2, "Dave", "clean_the_windows", 0.5, 1.5)]);
+    assert_eq!(verify::verify(&run, &limits()), [Violation::RobotOverlap {
+        robot: "Dave".into(), first: 1, second: 2, at: secs(0.5), first_end: secs(1.0),
+    }]);
+}
+
+#[test]
+fn starts_obey_the_ratelimit() {
+    let run = timeline(vec![entry(1, "Dave", "feed_the_cat", 0.0, 0.5), entry(2, "Cris", "feed_the_cat", 1.0, 1.5)]);
+    assert_eq!(verify::verify(&run, &limits()), [Violation::RateLimit {
+        task: "feed_the_cat".into(), id: 2, at: secs(1.0), previous: 1, previous_at: secs(0.0), rate: Rate::every(secs(2.0)),
+    }]);
+}
+
+#[test]
+fn a_burst_allows_that_many_starts_at_once() {
+    let mut limits = limits();
+    limits.concurrency = 3;
+    let run = timeline(vec![
+        entry(1, "Dave", "water_the_plants", 0.0, 0.7),
+        entry(2, "Cris", "water_the_plants", 0.0, 0.7),
+        entry(3, "Andi", "water_the_plants", 0.0, 0.7),
+        // the bucket refills one start a second
+        entry(4, "Dave", "water_the_plants", 1.0, 1.7),
+    ]);
+    // the third start is one too many, counted from the start 2 before it
+    assert_eq!(verify::verify(&run, &limits), [Violation::RateLimit {
+        task: "water_the_plants".into(), id: 3, at: secs(0.0), previous: 1, previous_at: secs(0.0), rate: Rate::every(secs(1.0)).burst(2),
+    }]);
+}
+
+#[test]
+fn robots_keep_the_order_of_their_queue() {
+    let run = Timeline {
+        entries: vec![entry(1, "Dave", "clean_the_windows", 0.0, 0.5), entry(2, "Dave", "clean_the_windows", 1.0, 1.5)],
+        order: vec![2, 1],
+    };
+    assert_eq!(verify::verify(&run, &limits()), [Violation::Order {
+        robot: "Dave".into(), id: 1, at: secs(0.0), queued_before: 2, queued_before_at: secs(1.0),
+    }]);
+}
+
+#[test]
+fn tasks_without_a_ratelimit_are_reported() {
+    let run = timeline(vec![entry(1, "Dave", "mow_the_lawn", 0.0, 0.5)]);
+    assert_eq!(verify::verify(&run, &limits()), [Violation::UnknownTask { task: "mow_the_lawn".into(), id: 1 }]);
+}
+
+fn spans(timeline: &Timeline) -> Vec<(usize, String, String, Duration, Duration)> {
+    let mut spans: Vec<_> = timeline.entries.iter().map(|e| (e.id, e.robot.clone(), e.task.clone(), e.start, e.end)).collect();
+    spans.sort();
+    spans
+}
+
+#[tokio::test(start_paused = true)]
+async fn the_json_events_read_back_as_the_run() {
+    let lines = Arc::new(Mutex::new(String::new()));
+    let printer = lines.clone();
+    let executor = Executor::builder()
+        .robots(["Dave", "Cris"])
+        .task("feed_the_cat", Duration::from_secs(2), chore(500, "Meow"))
+        .concurrency(1)
+        .strategy(Strategy::Optimized)
+        // what `--json` prints
+        .subscriber(move |event: &ExecutorEvent| {
+            let mut lines = printer.lock().unwrap();
+            lines.push_str(&serde_json::to_string(event).unwrap());
+            lines.push('\n');
+        })
+        .build()
+        .unwrap();
+    let report = executor.run([(1, "Dave", "feed_the_cat"), (2, "Cris", "feed_the_cat"), (3, "Dave", "feed_the_cat")]).await.unwrap();
+
+    let events = Timeline::parse(&lines.lock().unwrap()).unwrap();
+    assert_eq!(events.order, [1, 2, 3]);
+    assert_eq!(spans(&events), spans(&Timeline::from_report(&report)));
+    assert_eq!(verify::verify(&events, &executor.limits()), []);
+
+    // the same stream with the second start, Dave's id 3 once the cat can be fed again, a second earlier
+    let started = r#"{"event":"task","at":2.0,"robot":"Dave","id":3,"task":"feed_the_cat","phase":"started"}"#;
+    assert!(lines.lock().unwrap().contains(started));
+    let early = lines.lock().unwrap().replacen(started, &started.replacen("2.0", "1.0", 1), 1);
+    assert_eq!(verify::verify(&Timeline::parse(&early).unwrap(), &executor.limits()), [Violation::RateLimit {
+        task: "feed_the_cat".into(), id: 3, at: secs(1.0), previous: 1, previous_at: secs(0.0), rate: Rate::every(secs(2.0)),
+    }]);
+}
+
+// submitted at the same instant, not in the order of their ids
+#[tokio::test(start_paused = true)]
+async fn reports_keep_the_order_jobs_were_submitted_in() {
+    for strategy in STRATEGIES {
+        let executor = Executor::builder()
+            .robot("Dave")
+            .task("feed_the_cat", Duration::from_secs(2), chore(0, "Meow"))
+            .strategy(strategy)
+            .quiet()
+            .build()
+            .unwrap();
+        let report = executor.run([(3, "Dave", "feed_the_cat"), (1, "Dave", "feed_the_cat"), (2, "Dave", "feed_the_cat")]).await.unwrap();
+        let run = Timeline::from_report(&report);
+        assert_eq!(run.order, [3, 1, 2], "{strategy:?}");
+        assert_eq!(verify::verify(&run, &executor.limits()), [], "{strategy:?}");
+    }
+}
+
+#[test]
+fn unreadable_lines_are_pointed_at() {
+    assert!(matches!(Timeline::parse("\n{\"nope\":1}\n"), Err(robot_tech_test::Error::Parse { line: 2, .. })));
+}